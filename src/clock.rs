@@ -0,0 +1,103 @@
+//! [Clock]s for stamping [LogObject](crate::loggers::LogObject)s with a time.
+
+use std::{
+    sync::{Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The source of [LogObject::time](crate::loggers::LogObject::time) used by a logger (see [Stamping](crate::loggers::Stamping)).
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Clock {
+    /// [SystemTime::now()].
+    #[default]
+    System,
+    /// The [SystemTime] of the first use of any [Clock::Monotonic] advanced by a monotonic [Instant].
+    ///
+    /// Never goes backwards, even if the system time is adjusted.
+    Monotonic,
+    /// Always the same [SystemTime].
+    Fixed(SystemTime),
+    /// A [ManualClock] that only advances when told to.
+    Manual(&'static ManualClock),
+}
+
+impl Clock {
+    /// Returns the current time according to this clock.
+    #[must_use]
+    pub fn now(&self) -> SystemTime {
+        match self {
+            Clock::System => SystemTime::now(),
+            Clock::Monotonic => {
+                static ANCHOR: OnceLock<(SystemTime, Instant)> = OnceLock::new();
+                let (time, instant) = ANCHOR.get_or_init(|| (SystemTime::now(), Instant::now()));
+                *time + instant.elapsed()
+            },
+            Clock::Fixed(time) => *time,
+            Clock::Manual(clock) => clock.now(),
+        }
+    }
+}
+
+/// A clock that only advances when told to.
+///
+/// Used as a `static` (or leaked) so that [Clock] stays [Copy].
+///
+/// ```
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// # use logidize::clock::{Clock, ManualClock};
+/// static MANUAL: ManualClock = ManualClock::new(UNIX_EPOCH);
+/// let clock = Clock::Manual(&MANUAL);
+/// MANUAL.advance(Duration::from_secs(5));
+/// assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(5));
+/// ```
+#[derive(Debug)]
+pub struct ManualClock(Mutex<SystemTime>);
+
+impl ManualClock {
+    /// Constructs a new [ManualClock] starting at `time`.
+    #[must_use]
+    pub const fn new(time: SystemTime) -> Self {
+        Self(Mutex::new(time))
+    }
+
+    /// Returns the clock's current time.
+    #[must_use]
+    pub fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets the clock's current time.
+    pub fn set(&self, time: SystemTime) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = time;
+    }
+
+    /// Advances the clock's current time.
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    #[test]
+    fn test_clocks() {
+        let before = SystemTime::now();
+        let system = Clock::System.now();
+        assert!(before <= system && system <= SystemTime::now());
+        let first = Clock::Monotonic.now();
+        let second = Clock::Monotonic.now();
+        assert!(first <= second);
+        let fixed = Clock::Fixed(UNIX_EPOCH);
+        assert_eq!(fixed.now(), UNIX_EPOCH);
+        static MANUAL: ManualClock = ManualClock::new(UNIX_EPOCH);
+        let clock = Clock::Manual(&MANUAL);
+        MANUAL.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_millis(1500));
+        MANUAL.set(before);
+        assert_eq!(clock.now(), before);
+    }
+}
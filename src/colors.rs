@@ -1,8 +1,8 @@
-//! Colored output with [Level]-wrapper.
+//! Colored output with [Level]-wrapper and configurable [Theme]s.
 
-use std::fmt::Display;
+use std::{env, ffi::OsString, fmt::Display};
 
-use crate::loggers::Level;
+use crate::{loggers::Level, sinks::splitmix64};
 use const_format::concatcp;
 
 /// ANSI color-code for bright red.
@@ -47,3 +47,318 @@ impl Display for Colored {
         f.write_str(self.as_str())
     }
 }
+
+/// A terminal color.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Color {
+    /// One of the 16 standard colors (`0..8` normal, `8..16` bright) in the order
+    /// black, red, green, yellow, blue, magenta, cyan, white.
+    Ansi(u8),
+    /// One of the 256 colors of the extended palette.
+    Ansi256(u8),
+    /// A 24-bit truecolor.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Standard black.
+    pub const BLACK   : Color = Color::Ansi(0);
+    /// Standard red.
+    pub const RED     : Color = Color::Ansi(1);
+    /// Standard green.
+    pub const GREEN   : Color = Color::Ansi(2);
+    /// Standard yellow.
+    pub const YELLOW  : Color = Color::Ansi(3);
+    /// Standard blue.
+    pub const BLUE    : Color = Color::Ansi(4);
+    /// Standard magenta.
+    pub const MAGENTA : Color = Color::Ansi(5);
+    /// Standard cyan.
+    pub const CYAN    : Color = Color::Ansi(6);
+    /// Standard white.
+    pub const WHITE   : Color = Color::Ansi(7);
+}
+
+/// A terminal text style consisting of an optional foreground [Color] and attributes.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Style {
+    /// Whether the text is bold (or bright on some terminals).
+    pub bold: bool,
+    /// Whether the text is dimmed.
+    pub dim: bool,
+    /// The foreground [Color] or [None] for the terminal's default.
+    pub foreground: Option<Color>,
+    /// Whether the text is underlined.
+    pub underline: bool,
+}
+
+impl Style {
+    /// The [Style] that doesn't change the text's appearance.
+    pub const NONE: Style = Style { bold: false, dim: false, foreground: None, underline: false };
+
+    /// Constructs a new [Style] with the given foreground [Color].
+    ///
+    /// ```
+    /// # use logidize::colors::{Color, Style, SET_COLOR_BRIGHT_RED};
+    /// assert_eq!(Style::fg(Color::RED).bold().to_string(), SET_COLOR_BRIGHT_RED);
+    /// assert_eq!(Style::fg(Color::Rgb(1, 2, 3)).underline().to_string(), "\x1b[4;38;2;1;2;3m");
+    /// assert_eq!(Style::NONE.to_string(), "");
+    /// ```
+    #[must_use]
+    pub const fn fg(color: Color) -> Self {
+        Style { foreground: Some(color), ..Style::NONE }
+    }
+
+    /// Returns a bold version of the [Style].
+    #[must_use]
+    pub const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    /// Returns a dimmed version of the [Style].
+    #[must_use]
+    pub const fn dim(self) -> Self {
+        Style { dim: true, ..self }
+    }
+
+    /// Returns an underlined version of the [Style].
+    #[must_use]
+    pub const fn underline(self) -> Self {
+        Style { underline: true, ..self }
+    }
+
+    /// Returns whether the [Style] doesn't change the text's appearance.
+    #[must_use]
+    pub const fn is_none(&self) -> bool {
+        !self.bold && !self.dim && !self.underline && self.foreground.is_none()
+    }
+}
+
+/// Displays the ANSI escape-sequence setting the [Style] (nothing for [Style::NONE]).
+impl Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_none() {
+            return Ok(());
+        }
+        let mut separator = "";
+        f.write_str("\x1b[")?;
+        for (enabled, code) in [(self.bold, "1"), (self.dim, "2"), (self.underline, "4")] {
+            if enabled {
+                write!(f, "{separator}{code}")?;
+                separator = ";";
+            }
+        }
+        match self.foreground {
+            None => (),
+            Some(Color::Ansi(n @ 0..=7)) => write!(f, "{separator}{}", 30 + n)?,
+            Some(Color::Ansi(n)) => write!(f, "{separator}{}", 90 + (n - 8) % 8)?,
+            Some(Color::Ansi256(n)) => write!(f, "{separator}38;5;{n}")?,
+            Some(Color::Rgb(r, g, b)) => write!(f, "{separator}38;2;{r};{g};{b}")?,
+        }
+        f.write_str("m")
+    }
+}
+
+/// Displays a value in a [Style], resetting the style afterwards.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Styled<T: Display>(pub Style, pub T);
+
+impl<T: Display> Display for Styled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.is_none() {
+            true => self.1.fmt(f),
+            false => write!(f, "{}{}{RESET_COLOR}", self.0, self.1),
+        }
+    }
+}
+
+/// The [Style]s used for the parts of formatted [LogObject](crate::loggers::LogObject)s.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Theme {
+    /// The [Style] of channels that neither have an entry in [Theme::channels] nor a color from [Theme::channel_palette].
+    pub channel: Style,
+    /// Colors automatically assigned to channels by their ID (if not empty).
+    ///
+    /// Colors are picked by a hash of the channel ID, so they are stable across runs and spread over the palette.
+    pub channel_palette: &'static [Color],
+    /// The [Style]s of specific channels by ID.
+    pub channels: &'static [(usize, Style)],
+    /// The [Style]s of the levels from [Level::DEBUG] to [Level::CRITICAL].
+    pub levels: [Style; 5],
+    /// The [Style] of thread IDs.
+    pub thread: Style,
+    /// The [Style] of timestamps.
+    pub time: Style,
+}
+
+impl Theme {
+    /// A [Theme] without any styling.
+    pub const PLAIN: Theme = Theme {
+        channel: Style::NONE,
+        channel_palette: &[],
+        channels: &[],
+        levels: [Style::NONE; 5],
+        thread: Style::NONE,
+        time: Style::NONE,
+    };
+
+    /// The classic 16-color [Theme] matching [Colored].
+    pub const CLASSIC: Theme = Theme {
+        channel: Style::fg(Color::WHITE).bold(),
+        channel_palette: &[],
+        channels: &[],
+        levels: [
+            Style::fg(Color::CYAN).bold(),
+            Style::fg(Color::BLUE).bold(),
+            Style::fg(Color::YELLOW).bold(),
+            Style::fg(Color::RED).bold(),
+            Style::fg(Color::MAGENTA).bold(),
+        ],
+        thread: Style::fg(Color::WHITE).bold(),
+        time: Style::fg(Color::GREEN).bold(),
+    };
+
+    /// A 256-color [Theme] with automatically colored channels.
+    pub const PALETTE_256: Theme = Theme {
+        channel: Style::NONE,
+        channel_palette: &[
+            Color::Ansi256(39), Color::Ansi256(208), Color::Ansi256(76), Color::Ansi256(170),
+            Color::Ansi256(220), Color::Ansi256(44), Color::Ansi256(203), Color::Ansi256(141),
+        ],
+        channels: &[],
+        levels: [
+            Style::fg(Color::Ansi256(245)),
+            Style::fg(Color::Ansi256(33)).bold(),
+            Style::fg(Color::Ansi256(214)).bold(),
+            Style::fg(Color::Ansi256(196)).bold(),
+            Style::fg(Color::Ansi256(201)).bold().underline(),
+        ],
+        thread: Style::fg(Color::Ansi256(244)),
+        time: Style::fg(Color::Ansi256(244)).dim(),
+    };
+
+    /// A 24-bit truecolor [Theme] with automatically colored channels.
+    pub const TRUECOLOR: Theme = Theme {
+        channel: Style::NONE,
+        channel_palette: &[
+            Color::Rgb(97, 175, 239), Color::Rgb(209, 154, 102), Color::Rgb(152, 195, 121), Color::Rgb(198, 120, 221),
+            Color::Rgb(229, 192, 123), Color::Rgb(86, 182, 194), Color::Rgb(224, 108, 117), Color::Rgb(171, 178, 191),
+        ],
+        channels: &[],
+        levels: [
+            Style::fg(Color::Rgb(128, 128, 128)),
+            Style::fg(Color::Rgb(97, 175, 239)).bold(),
+            Style::fg(Color::Rgb(229, 192, 123)).bold(),
+            Style::fg(Color::Rgb(224, 108, 117)).bold(),
+            Style::fg(Color::Rgb(255, 85, 255)).bold().underline(),
+        ],
+        thread: Style::fg(Color::Rgb(128, 128, 128)),
+        time: Style::fg(Color::Rgb(128, 128, 128)).dim(),
+    };
+
+    /// Returns the [Style] of the level.
+    #[must_use]
+    pub const fn level(&self, level: Level) -> Style {
+        self.levels[level as usize]
+    }
+
+    /// Returns the [Style] of the channel.
+    ///
+    /// ```
+    /// # use logidize::colors::{Color, Style, Theme};
+    /// const THEME: Theme = Theme { channels: &[(3, Style::fg(Color::RED))], ..Theme::PALETTE_256 };
+    /// let theme = THEME;
+    /// assert_eq!(theme.channel(3), Style::fg(Color::RED));
+    /// assert_ne!(theme.channel(1), theme.channel(2));
+    /// assert_eq!(theme.channel(1), Theme::PALETTE_256.channel(1));
+    /// ```
+    #[must_use]
+    pub fn channel(&self, channel_id: usize) -> Style {
+        if let Some((_, style)) = self.channels.iter().find(|(id, _)| *id == channel_id) {
+            return *style;
+        }
+        match self.channel_palette.len() {
+            0 => self.channel,
+            n => Style { foreground: Some(self.channel_palette[(channel_hash(channel_id) % n as u64) as usize]), ..self.channel },
+        }
+    }
+}
+
+/// Mixes the bits of a channel ID (using the SplitMix64 finalizer) so that palette colors don't repeat with a fixed period.
+const fn channel_hash(channel_id: usize) -> u64 {
+    splitmix64(channel_id as u64)
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::CLASSIC
+    }
+}
+
+/// Determines whether output is colored.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Output is always colored.
+    Always,
+    /// Output is never colored.
+    Never,
+    /// Output is colored if it goes to a terminal unless the environment says otherwise.
+    ///
+    /// `NO_COLOR` (non-empty) disables colors, then `CLICOLOR_FORCE` (non-empty and not `0`) enables colors,
+    /// then `CLICOLOR=0` and `TERM=dumb` disable colors.
+    #[default]
+    Auto,
+}
+
+impl ColorMode {
+    /// Returns whether output to a terminal (or not) should be colored.
+    #[must_use]
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => auto_enabled(is_terminal, |name| env::var_os(name)),
+        }
+    }
+}
+
+fn auto_enabled(is_terminal: bool, var: impl Fn(&str) -> Option<OsString>) -> bool {
+    let is = |name, value: &str| var(name).is_some_and(|v| v == value);
+    let non_empty = |name| var(name).is_some_and(|v| !v.is_empty());
+    if non_empty("NO_COLOR") {
+        return false;
+    }
+    if non_empty("CLICOLOR_FORCE") && !is("CLICOLOR_FORCE", "0") {
+        return true;
+    }
+    if is("CLICOLOR", "0") || is("TERM", "dumb") {
+        return false;
+    }
+    is_terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_enabled_with(is_terminal: bool, vars: &[(&str, &str)]) -> bool {
+        auto_enabled(is_terminal, |name| {
+            vars.iter().find(|(n, _)| *n == name).map(|(_, value)| value.into())
+        })
+    }
+
+    #[test]
+    fn test_auto() {
+        assert!(auto_enabled_with(true, &[]));
+        assert!(!auto_enabled_with(false, &[]));
+        assert!(!auto_enabled_with(true, &[("NO_COLOR", "1")]));
+        assert!(auto_enabled_with(true, &[("NO_COLOR", "")]));
+        assert!(!auto_enabled_with(true, &[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]));
+        assert!(auto_enabled_with(false, &[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")]));
+        assert!(!auto_enabled_with(false, &[("CLICOLOR_FORCE", "0")]));
+        assert!(!auto_enabled_with(true, &[("CLICOLOR", "0")]));
+        assert!(auto_enabled_with(true, &[("CLICOLOR", "1")]));
+        assert!(!auto_enabled_with(true, &[("TERM", "dumb")]));
+        assert!(auto_enabled_with(true, &[("TERM", "xterm-256color")]));
+    }
+}
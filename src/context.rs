@@ -0,0 +1,232 @@
+//! A thread-local diagnostic context (e.g. request IDs) attached to every [LogObject](crate::loggers::LogObject) logged on the thread.
+//!
+//! ```
+//! # use logidize::{*, context, loggers::single_threaded::SimpleLogger, sinks::CaptureSink};
+//! let capture = CaptureSink::new();
+//! let logger = SimpleLogger::new(capture.clone());
+//! {
+//!     let _request = context::push("request_id", 42);
+//!     let _user = context::push("user", "alice");
+//!     info!(logger, "handling request");
+//! }
+//! info!(logger, "idle");
+//! let records = capture.records();
+//! assert_eq!(records[0].context.as_context().to_string(), "request_id=42 user=alice");
+//! assert!(records[1].context.as_context().is_empty());
+//! ```
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    marker::PhantomData,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+type Entries = Vec<(&'static str, String)>;
+
+thread_local! {
+    static CURRENT: RefCell<ContextSnapshot> = const { RefCell::new(ContextSnapshot(None)) };
+}
+
+/// Pushes a key/value pair onto the calling thread's diagnostic context until the returned [ContextGuard] is dropped.
+///
+/// The value is rendered immediately.
+#[must_use = "the pair is popped immediately if the guard is not kept alive"]
+pub fn push(key: &'static str, value: impl Display) -> ContextGuard {
+    let value = value.to_string();
+    CURRENT.with_borrow_mut(|current| {
+        let entries = Arc::make_mut(current.0.get_or_insert_default());
+        let depth = entries.len();
+        entries.push((key, value));
+        ContextGuard { depth, _unsend: PhantomData }
+    })
+}
+
+/// Returns a [ContextSnapshot] of the calling thread's diagnostic context.
+#[must_use]
+pub fn current() -> ContextSnapshot {
+    CURRENT.try_with(|current| current.borrow().clone()).unwrap_or_default()
+}
+
+/// Spawns a thread (see [thread::spawn()]) that inherits the calling thread's diagnostic context.
+///
+/// ```
+/// # use logidize::context;
+/// let _guard = context::push("job", 7);
+/// let inherited = context::spawn(|| context::current().as_context().get("job").map(str::to_owned)).join().unwrap();
+/// assert_eq!(inherited.as_deref(), Some("7"));
+/// ```
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(current().wrap(f))
+}
+
+/// Pops the key/value pair pushed by [push()] (and any pushed after it that are still on the stack) when dropped.
+#[derive(Debug)]
+#[must_use = "the pair is popped immediately if the guard is not kept alive"]
+pub struct ContextGuard {
+    depth: usize,
+    _unsend: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|current| {
+            let mut current = current.borrow_mut();
+            match self.depth {
+                0 => current.0 = None,
+                depth => if let Some(entries) = &mut current.0 {
+                    if entries.len() > depth {
+                        Arc::make_mut(entries).truncate(depth);
+                    }
+                },
+            }
+        });
+    }
+}
+
+/// An owned, cheaply cloneable copy of a thread's diagnostic context.
+///
+/// Obtained with [current()].
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct ContextSnapshot(Option<Arc<Entries>>);
+
+impl ContextSnapshot {
+    /// Returns a [Context] borrowing this snapshot.
+    #[must_use]
+    pub const fn as_context(&self) -> Context<'_> {
+        Context(match &self.0 {
+            Some(entries) => Some(entries),
+            None => None,
+        })
+    }
+
+    /// Pushes all key/value pairs of this snapshot onto the calling thread's diagnostic context
+    /// until the returned [ContextGuard] is dropped.
+    #[must_use = "the pairs are popped immediately if the guard is not kept alive"]
+    pub fn enter(&self) -> ContextGuard {
+        CURRENT.with_borrow_mut(|current| match (&mut current.0, &self.0) {
+            (None, entries) => {
+                current.0 = entries.clone();
+                ContextGuard { depth: 0, _unsend: PhantomData }
+            },
+            (Some(current), entries) => {
+                let current = Arc::make_mut(current);
+                let depth = current.len();
+                current.extend(entries.iter().flat_map(|entries| entries.iter().cloned()));
+                ContextGuard { depth, _unsend: PhantomData }
+            },
+        })
+    }
+
+    /// Wraps `f` so that it runs with this snapshot entered (e.g. on a thread pool).
+    pub fn wrap<F: FnOnce() -> T, T>(self, f: F) -> impl FnOnce() -> T {
+        move || {
+            let _guard = self.enter();
+            f()
+        }
+    }
+}
+
+/// The diagnostic context of a [LogObject](crate::loggers::LogObject): key/value pairs from the outermost to the innermost.
+///
+/// Displayed as space-separated `key=value` pairs.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Context<'a>(Option<&'a Arc<Entries>>);
+
+impl<'a> Context<'a> {
+    /// The empty [Context].
+    pub const EMPTY: Context<'static> = Context(None);
+
+    /// Returns an iterator over the key/value pairs from the outermost to the innermost.
+    pub fn iter(self) -> impl Iterator<Item = (&'static str, &'a str)> {
+        self.entries().iter().map(|(key, value)| (*key, value.as_str()))
+    }
+
+    /// Returns the innermost value of the key (if any).
+    #[must_use]
+    pub fn get(self, key: &str) -> Option<&'a str> {
+        self.entries().iter().rev().find(|(k, _)| *k == key).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the number of key/value pairs.
+    #[must_use]
+    pub fn len(self) -> usize {
+        self.entries().len()
+    }
+
+    /// Returns whether there are no key/value pairs.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Returns an owned [ContextSnapshot] of this context.
+    #[must_use]
+    pub fn to_snapshot(self) -> ContextSnapshot {
+        ContextSnapshot(self.0.cloned())
+    }
+
+    fn entries(self) -> &'a [(&'static str, String)] {
+        match self.0 {
+            Some(entries) => entries,
+            None => &[],
+        }
+    }
+}
+
+impl Display for Context<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack() {
+        assert!(current().as_context().is_empty());
+        let outer = push("request_id", 1);
+        {
+            let _inner = push("request_id", 2);
+            let _user = push("user", "bob");
+            let snapshot = current();
+            assert_eq!(snapshot.as_context().get("request_id"), Some("2"));
+            assert_eq!(snapshot.as_context().to_string(), "request_id=1 request_id=2 user=bob");
+        }
+        assert_eq!(current().as_context().to_string(), "request_id=1");
+        drop(outer);
+        assert_eq!(current(), ContextSnapshot::default());
+    }
+
+    #[test]
+    fn test_threads() {
+        let snapshot = {
+            let _guard = push("job", 3);
+            current()
+        };
+        assert!(current().as_context().is_empty());
+        let wrapped = snapshot.clone().wrap(current);
+        assert_eq!(thread::spawn(wrapped).join().unwrap(), snapshot);
+        let _outer = push("worker", 1);
+        {
+            let _entered = snapshot.enter();
+            assert_eq!(current().as_context().to_string(), "worker=1 job=3");
+        }
+        assert_eq!(current().as_context().to_string(), "worker=1");
+        let spawned = spawn(|| current().as_context().to_string()).join().unwrap();
+        assert_eq!(spawned, "worker=1");
+    }
+}
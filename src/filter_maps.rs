@@ -2,7 +2,10 @@
 
 use std::{collections::btree_map::{BTreeMap, Entry}, fmt::Display, ptr::NonNull};
 
-use crate::loggers::{Level, LogObject};
+use crate::{
+    loggers::{Level, LogObject},
+    sinks::{Sampling, SamplingCounts, SamplingRng},
+};
 
 /// A trait for displaying channels of [LogObject]s or discarding them.
 pub trait ChannelFilterMap {
@@ -228,3 +231,74 @@ impl<T: Display> ChannelFilterMap for SimpleChannelFilterMap<T> {
         Some(BorrowDisplay(ptr))
     }
 }
+
+/// A [ChannelFilterMap] that discards all but a sample of the [LogObject]s logged by another [ChannelFilterMap].
+///
+/// Behaves like [SamplingSink](crate::sinks::SamplingSink) but can be used as the [ChannelFilterMap] of any sink.
+///
+/// ```
+/// # use logidize::{*, filter_maps::{InvisibleChannelFilterMap, SamplingFilterMap}, loggers::single_threaded::SimpleLogger, sinks::{Sampling, WriteSink}};
+/// let mut channel_map = SamplingFilterMap::new(InvisibleChannelFilterMap, Sampling::EveryNth(100));
+/// channel_map.channel_sampling.insert(1, Sampling::All);
+/// let logger = SimpleLogger::new(WriteSink::new(Vec::new(), channel_map));
+/// for i in 0..1000 {
+///     debug!(logger, "sampled {i}");
+///     debug!(logger.channel(1), "kept {i}");
+/// }
+/// assert_eq!(logger.sink().channel_map.sampled_out(), 990);
+/// assert_eq!(logger.sink().channel_map.channel_sampled_out(1), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SamplingFilterMap<M: ChannelFilterMap> {
+    /// The [Sampling]s of channels that don't use [SamplingFilterMap::default_sampling].
+    pub channel_sampling: BTreeMap<usize, Sampling>,
+    /// The [Sampling] of channels without an entry in [SamplingFilterMap::channel_sampling].
+    pub default_sampling: Sampling,
+    /// The underlying [ChannelFilterMap].
+    pub map: M,
+    /// The maximum sampled severity level. [LogObject]s of higher severity are never sampled out.
+    pub max_severity: Level,
+    /// The [SamplingRng] used for [Sampling::Rate].
+    pub rng: SamplingRng,
+    counts: SamplingCounts,
+}
+
+impl<M: ChannelFilterMap> SamplingFilterMap<M> {
+    /// Constructs a new [SamplingFilterMap] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub fn new(map: M, default_sampling: Sampling) -> Self {
+        Self {
+            channel_sampling: BTreeMap::new(),
+            default_sampling,
+            map,
+            max_severity: Level::CRITICAL,
+            rng: SamplingRng::from_random_seed(),
+            counts: SamplingCounts::new(),
+        }
+    }
+
+    /// Returns the total number of [LogObject]s sampled out by this map.
+    #[must_use]
+    pub const fn sampled_out(&self) -> u64 {
+        self.counts.sampled_out()
+    }
+
+    /// Returns the number of [LogObject]s of the channel sampled out by this map.
+    #[must_use]
+    pub fn channel_sampled_out(&self, channel_id: usize) -> u64 {
+        self.counts.channel_sampled_out(channel_id)
+    }
+}
+
+impl<M: ChannelFilterMap> ChannelFilterMap for SamplingFilterMap<M> {
+    type DisplayType = M::DisplayType;
+
+    fn filter_map(&mut self, log_object: &LogObject) -> Option<Self::DisplayType> {
+        let display = self.map.filter_map(log_object)?;
+        if log_object.severity > self.max_severity {
+            return Some(display);
+        }
+        let sampling = *self.channel_sampling.get(&log_object.channel_id).unwrap_or(&self.default_sampling);
+        self.counts.sample(log_object.channel_id, sampling, &mut self.rng).then_some(display)
+    }
+}
@@ -2,7 +2,9 @@
 
 #![warn(missing_docs)]
 
+pub mod clock;
 pub mod colors;
+pub mod context;
 pub mod filter_maps;
 pub mod loggers;
 pub mod sinks;
@@ -15,13 +17,15 @@ use crate::{
 	filter_maps::SimpleChannelFilterMap,
 	loggers::multi_threaded::SimpleLogger,
 	sinks::WriteSink,
-	writers::StderrWriter,
+	writers::AtomicStderrWriter,
 };
 
 // Default::default() is not const
 /// A sensible default logger for use in multithreaded applications.
-pub static GLOBAL_LOGGER: SimpleLogger<WriteSink<StderrWriter, SimpleChannelFilterMap<String>>> = SimpleLogger::new(
-    WriteSink::new(StderrWriter, SimpleChannelFilterMap::new())
+///
+/// Records are written with [AtomicStderrWriter], so they don't interleave with other output.
+pub static GLOBAL_LOGGER: SimpleLogger<WriteSink<AtomicStderrWriter, SimpleChannelFilterMap<String>>> = SimpleLogger::new(
+    WriteSink::new(AtomicStderrWriter::new(), SimpleChannelFilterMap::new())
 );
 
 /// Invoked to retrieve a default [Logger](loggers::Logger) in logging-macros like [log!].
@@ -116,3 +120,17 @@ macro_rules! critical {
         $logger.critical(format_args!($($args)*))
     };
 }
+
+/// Enters a [Span](loggers::Span) named using [format_args!] that logs its entry now and its exit with the elapsed time when dropped.
+///
+/// Defaults to using [default_logger!].
+#[macro_export]
+macro_rules! timed {
+    ($lvl:expr, $fmt:literal $(, $($args:tt)*)?) => {
+        $crate::timed!(default_logger!(), $lvl, $fmt $(, $($args)*)?)
+    };
+
+    ($logger:expr, $lvl:expr, $($args:tt)+) => {
+        $crate::loggers::Span::enter(&$logger, $lvl, format_args!($($args)+))
+    };
+}
@@ -2,11 +2,27 @@
 
 pub mod single_threaded;
 pub mod multi_threaded;
+mod span;
 
-use std::{fmt::Display, thread::{self, ThreadId}, time::SystemTime};
+use std::{
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    panic::Location,
+    sync::atomic::{AtomicU64, Ordering},
+    thread::{self, ThreadId},
+    time::SystemTime,
+};
 #[doc(no_inline)]
 pub use std::fmt::Arguments;
 
+pub use span::Span;
+
+use crate::{
+    clock::Clock,
+    context::{Context, ContextSnapshot},
+};
+
 /// A logging severity level.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Level {
@@ -41,6 +57,240 @@ impl Display for Level {
     }
 }
 
+/// Identity of a thread that is safe to print.
+///
+/// Obtained with [ThreadInfo::current()].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThreadInfo {
+    /// A stable numeric ID unique within the process.
+    ///
+    /// IDs are assigned in the order threads first use [ThreadInfo::current()], starting at `1`.
+    pub id: u64,
+    /// The thread's name (if set).
+    pub name: Option<ThreadName>,
+    /// The thread ID assigned by the operating system (only available on Linux).
+    pub os_id: Option<u64>,
+}
+
+impl ThreadInfo {
+    /// Returns the [ThreadInfo] of the calling thread.
+    ///
+    /// ```
+    /// # use logidize::loggers::ThreadInfo;
+    /// let info = ThreadInfo::current();
+    /// assert_eq!(info, ThreadInfo::current());
+    /// let other = std::thread::Builder::new().name("worker".into()).spawn(ThreadInfo::current).unwrap().join().unwrap();
+    /// assert_ne!(info.id, other.id);
+    /// assert_eq!(other.name.as_deref(), Some("worker"));
+    /// ```
+    #[must_use]
+    pub fn current() -> Self {
+        thread_local! {
+            static CURRENT: ThreadInfo = ThreadInfo::detect();
+        }
+        CURRENT.try_with(|info| *info).unwrap_or_else(|_| ThreadInfo::detect())
+    }
+
+    fn detect() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let name = thread::current().name().map(ThreadName::new);
+        Self { id: NEXT_ID.fetch_add(1, Ordering::Relaxed), name, os_id: os_thread_id() }
+    }
+
+    /// Returns a [Display] of this thread according to the [ThreadFormat].
+    #[must_use]
+    pub const fn display(self, format: ThreadFormat) -> DisplayThread {
+        DisplayThread(self, format)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn os_thread_id() -> Option<u64> {
+    u64::try_from(unsafe { libc::syscall(libc::SYS_gettid) }).ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_thread_id() -> Option<u64> {
+    None
+}
+
+/// A thread name stored inline, so that [ThreadInfo] stays [Copy].
+///
+/// Names longer than [ThreadName::CAPACITY] bytes are truncated at a character boundary.
+///
+/// ```
+/// # use logidize::loggers::ThreadName;
+/// assert_eq!(ThreadName::new("worker").as_str(), "worker");
+/// assert_eq!(ThreadName::new(&"ä".repeat(40)).len(), 62);
+/// ```
+#[derive(Clone, Copy)]
+pub struct ThreadName {
+    bytes: [u8; ThreadName::CAPACITY],
+    len: u8,
+}
+
+impl ThreadName {
+    /// The maximum length of a [ThreadName] in bytes.
+    pub const CAPACITY: usize = 63;
+
+    /// Constructs a new [ThreadName], truncating `name` to at most [ThreadName::CAPACITY] bytes.
+    #[must_use]
+    pub const fn new(name: &str) -> Self {
+        let mut len = if name.len() < Self::CAPACITY { name.len() } else { Self::CAPACITY };
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; Self::CAPACITY];
+        let mut i = 0;
+        while i < len {
+            bytes[i] = name.as_bytes()[i];
+            i += 1;
+        }
+        Self { bytes, len: len as u8 }
+    }
+
+    /// Returns the name as a [str].
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match std::str::from_utf8(self.bytes.split_at(self.len as usize).0) {
+            Ok(name) => name,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl Deref for ThreadName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for ThreadName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Debug for ThreadName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for ThreadName {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ThreadName {}
+
+impl PartialOrd for ThreadName {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ThreadName {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for ThreadName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+/// How a thread is displayed by [ThreadInfo::display()].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThreadFormat {
+    /// Displays [ThreadInfo::id].
+    #[default]
+    Id,
+    /// Displays [ThreadInfo::name] or [ThreadInfo::id] if the thread is unnamed.
+    Name,
+    /// Displays `name#id` or [ThreadInfo::id] if the thread is unnamed.
+    NameId,
+    /// Displays [ThreadInfo::os_id] or [ThreadInfo::id] if it isn't available.
+    OsId,
+}
+
+/// A [Display] of [ThreadInfo] created with [ThreadInfo::display()].
+///
+/// ```
+/// # use logidize::loggers::{ThreadFormat, ThreadInfo, ThreadName};
+/// let info = ThreadInfo { id: 7, name: Some(ThreadName::new("worker")), os_id: None };
+/// assert_eq!(info.display(ThreadFormat::Id).to_string(), "7");
+/// assert_eq!(info.display(ThreadFormat::Name).to_string(), "worker");
+/// assert_eq!(info.display(ThreadFormat::NameId).to_string(), "worker#7");
+/// assert_eq!(info.display(ThreadFormat::OsId).to_string(), "7");
+/// ```
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct DisplayThread(ThreadInfo, ThreadFormat);
+
+impl Display for DisplayThread {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ThreadInfo { id, name, os_id } = self.0;
+        match (self.1, name, os_id) {
+            (ThreadFormat::Name, Some(name), _) => f.write_str(&name),
+            (ThreadFormat::NameId, Some(name), _) => write!(f, "{name}#{id}"),
+            (ThreadFormat::OsId, _, Some(os_id)) => write!(f, "{os_id}"),
+            _ => write!(f, "{id}"),
+        }
+    }
+}
+
+/// How a logger stamps the [LogObject]s it creates with a time and a thread.
+///
+/// Overriding them makes the output deterministic (e.g. for golden-output tests).
+///
+/// ```
+/// # use std::time::UNIX_EPOCH;
+/// # use logidize::{clock::Clock, context::Context, loggers::{Level, Stamping, ThreadInfo, ThreadName}};
+/// let stamping = Stamping { clock: Clock::Fixed(UNIX_EPOCH), thread: Some(ThreadInfo { id: 7, name: Some(ThreadName::new("main")), os_id: None }) };
+/// let log_object = stamping.log_object(0, Level::INFO, format_args!("deterministic"), Context::EMPTY);
+/// assert_eq!(log_object.time, UNIX_EPOCH);
+/// assert_eq!(log_object.thread.id, 7);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stamping {
+    /// The [Clock] used for [LogObject::time].
+    pub clock: Clock,
+    /// The [ThreadInfo] used for [LogObject::thread] instead of [ThreadInfo::current()] (if set).
+    ///
+    /// [LogObject::thread_id] always refers to the logging thread.
+    pub thread: Option<ThreadInfo>,
+}
+
+impl Stamping {
+    /// Constructs a new [Stamping] using [Clock::System] and the logging thread.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { clock: Clock::System, thread: None }
+    }
+
+    /// Constructs a new [LogObject] stamped according to this [Stamping].
+    ///
+    /// Loggers pass the [Context] of a [current()](crate::context::current()) snapshot.
+    #[track_caller]
+    pub fn log_object<'a>(&self, channel_id: usize, severity: Level, message: Arguments<'a>, context: Context<'a>) -> LogObject<'a> {
+        LogObject {
+            channel_id,
+            context,
+            location: Location::caller(),
+            message,
+            severity,
+            thread: self.thread.unwrap_or_else(ThreadInfo::current),
+            thread_id: thread::current().id(),
+            time: self.clock.now(),
+        }
+    }
+}
+
 /// A log-message with metadata.
 ///
 /// Used by [single_threaded::SimpleLogger], [single_threaded::ChannelLogger], [multi_threaded::SimpleLogger], [multi_threaded::ChannelLogger].
@@ -51,54 +301,175 @@ pub struct LogObject<'a> {
     /// The main-channel (implicitly used by `SimpleLogger`s) has ID `0`.
     pub channel_id: usize,
 
+    /// The diagnostic [Context] of the logging thread when this [LogObject] was created by a logger.
+    pub context: Context<'a>,
+
+    /// The source location of the log-request (e.g. the invocation of [log!](crate::log!)).
+    pub location: &'static Location<'static>,
+
     /// The log-message supplied by a call to [Logger::log()] or its family.
     pub message: Arguments<'a>,
 
     /// The severity level used to log this message.
     pub severity: Level,
 
+    /// The [ThreadInfo] of the logging thread.
+    pub thread: ThreadInfo,
+
     /// The [ThreadId] of the logging thread.
     pub thread_id: ThreadId,
 
-    /// [SystemTime::now()] when this [LogObject] was created.
+    /// The time this [LogObject] was created according to the logger's [Clock] (usually [SystemTime::now()]).
     pub time: SystemTime,
 }
 
 impl LogObject<'_> {
     /// Constructs a new [LogObject] with information about call-time calling thread.
     ///
+    /// The [LogObject::context] is empty.
+    ///
     /// ```
     /// # use std::{thread, time::SystemTime};
-    /// # use logidize::loggers::{Level, LogObject};
+    /// # use logidize::loggers::{Level, LogObject, ThreadInfo};
     /// let log_object = LogObject::new(0, Level::DEBUG, format_args!("test"));
     /// assert_eq!(log_object.thread_id, thread::current().id());
+    /// assert_eq!(log_object.thread, ThreadInfo::current());
+    /// assert_eq!(log_object.location.line(), line!() - 3);
     /// assert_eq!(SystemTime::now().duration_since(log_object.time).unwrap().as_secs(), 0);
     /// ```
+    #[track_caller]
     pub fn new<'a>(channel_id: usize, severity: Level, message: Arguments<'a>) -> LogObject<'a> {
-        LogObject {
-            channel_id,
-            message,
-            severity,
-            thread_id: thread::current().id(),
-            time: SystemTime::now(),
+        Stamping::new().log_object(channel_id, severity, message, Context::EMPTY)
+    }
+
+    /// Renders [LogObject::message] into an owned [LogRecord].
+    #[must_use]
+    pub fn to_record(&self) -> LogRecord {
+        LogRecord {
+            channel_id: self.channel_id,
+            context: self.context.to_snapshot(),
+            location: self.location,
+            message: self.message.to_string(),
+            severity: self.severity,
+            thread: self.thread,
+            thread_id: self.thread_id,
+            time: self.time,
         }
     }
 }
 
+/// An owned version of [LogObject] with a rendered message.
+///
+/// Created with [LogObject::to_record()].
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct LogRecord {
+    /// See [LogObject::channel_id].
+    pub channel_id: usize,
+    /// See [LogObject::context].
+    pub context: ContextSnapshot,
+    /// See [LogObject::location].
+    pub location: &'static Location<'static>,
+    /// [LogObject::message] rendered to a [String].
+    pub message: String,
+    /// See [LogObject::severity].
+    pub severity: Level,
+    /// See [LogObject::thread].
+    pub thread: ThreadInfo,
+    /// See [LogObject::thread_id].
+    pub thread_id: ThreadId,
+    /// See [LogObject::time].
+    pub time: SystemTime,
+}
+
+impl LogRecord {
+    /// Calls `f` with a [LogObject] borrowing this record.
+    ///
+    /// ```
+    /// # use logidize::loggers::{Level, LogObject};
+    /// let record = LogObject::new(0, Level::DEBUG, format_args!("{}", 42)).to_record();
+    /// assert_eq!(record.message, "42");
+    /// record.with_log_object(|log_object| assert_eq!(log_object.to_record(), record));
+    /// ```
+    pub fn with_log_object<R>(&self, f: impl FnOnce(LogObject) -> R) -> R {
+        f(LogObject {
+            channel_id: self.channel_id,
+            context: self.context.as_context(),
+            location: self.location,
+            message: format_args!("{}", self.message),
+            severity: self.severity,
+            thread: self.thread,
+            thread_id: self.thread_id,
+            time: self.time,
+        })
+    }
+}
+
+impl From<LogObject<'_>> for LogRecord {
+    fn from(log_object: LogObject) -> Self {
+        log_object.to_record()
+    }
+}
+
 /// A trait for objects which are capable of logging [Arguments] with a severity [Level].
+///
+/// Implementations should be `#[track_caller]` so that [LogObject::location] refers to the log-request.
 pub trait Logger {
     /// Logs [Arguments] with severity [Level].
+    #[track_caller]
     fn log(&self, severity: Level, message: Arguments);
+    /// Logs [Arguments] with severity [Level] as if requested at `location` (e.g. for the exit of a [Span]).
+    ///
+    /// The default implementation ignores `location` and calls [Logger::log()].
+    #[track_caller]
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        let _ = location;
+        self.log(severity, message);
+    }
     /// Logs [Arguments] with severity [Level::DEBUG].
+    #[track_caller]
     fn    debug(&self, message: Arguments) { self.log(Level::DEBUG,    message); }
     /// Logs [Arguments] with severity [Level::INFO].
+    #[track_caller]
     fn     info(&self, message: Arguments) { self.log(Level::INFO,     message); }
     /// Logs [Arguments] with severity [Level::WARNING].
+    #[track_caller]
     fn  warning(&self, message: Arguments) { self.log(Level::WARNING,  message); }
     /// Logs [Arguments] with severity [Level::ERROR].
+    #[track_caller]
     fn    error(&self, message: Arguments) { self.log(Level::ERROR,    message); }
     /// Logs [Arguments] with severity [Level::CRITICAL].
+    #[track_caller]
     fn critical(&self, message: Arguments) { self.log(Level::CRITICAL, message); }
+
+    /// Flushes any output buffered by the logger.
+    ///
+    /// The default implementation does nothing.
+    fn flush(&self) {}
+
+    /// Returns a [FlushGuard] that flushes this logger when dropped.
+    ///
+    /// ```
+    /// # use logidize::{*, loggers::single_threaded::SimpleLogger, sinks::WriteSink};
+    /// let logger = SimpleLogger::new(WriteSink::<Vec<u8>>::default());
+    /// let _guard = logger.flush_guard();
+    /// info!(logger, "flushed when `_guard` goes out of scope");
+    /// ```
+    fn flush_guard(&self) -> FlushGuard<'_, Self> where Self: Sized {
+        FlushGuard(self)
+    }
+}
+
+/// Flushes the borrowed [Logger] when dropped.
+///
+/// Created with [Logger::flush_guard()].
+#[derive(Debug)]
+#[must_use = "the logger is flushed immediately if the guard is not kept alive"]
+pub struct FlushGuard<'a, L: Logger + ?Sized>(&'a L);
+
+impl<L: Logger + ?Sized> Drop for FlushGuard<'_, L> {
+    fn drop(&mut self) {
+        self.0.flush();
+    }
 }
 
 #[doc(hidden)]
@@ -108,6 +479,7 @@ pub struct MultiLogger<T1: Logger, T2: Logger>(pub T1, pub T2);
 macro_rules! impl_levels {
 	($($lvl:ident),*) => {
 		$(
+			#[track_caller]
 			fn $lvl(&self, message: Arguments) {
 				self.0.$lvl(message);
 				self.1.$lvl(message);
@@ -117,11 +489,23 @@ macro_rules! impl_levels {
 }
 
 impl<T1: Logger, T2: Logger> Logger for MultiLogger<T1, T2> {
+	#[track_caller]
 	fn log(&self, severity: Level, message: Arguments) {
 		self.0.log(severity, message);
 		self.1.log(severity, message);
 	}
 
+	#[track_caller]
+	fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+		self.0.log_at(location, severity, message);
+		self.1.log_at(location, severity, message);
+	}
+
+	fn flush(&self) {
+		self.0.flush();
+		self.1.flush();
+	}
+
 	impl_levels!(debug, info, warning, error, critical);
 }
 
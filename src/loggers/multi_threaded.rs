@@ -1,9 +1,13 @@
 //! [Logger]s for use in a multi-threaded context.
 
-use std::sync::{Mutex, LockResult, MutexGuard};
+use std::{
+    panic::Location,
+    sync::{Arc, Mutex, LockResult, MutexGuard},
+};
 
 use crate::{
-    loggers::{Arguments, Level, Logger, LogObject},
+    context,
+    loggers::{Arguments, Level, LogObject, Logger, Stamping},
     sinks::Sink,
 };
 
@@ -11,11 +15,15 @@ use crate::{
 #[derive(Debug, Default)]
 pub struct SimpleLogger<S: Sink> {
     sink: Mutex<S>,
+    stamping: Stamping,
 }
 
 impl<S: Sink + Clone> Clone for SimpleLogger<S> {
     fn clone(&self) -> Self {
-        Self { sink: Mutex::new(self.sink.lock().expect("SimpleLogger::clone() failed because the logger was poisoned").clone()) }
+        Self {
+            sink: Mutex::new(self.sink.lock().expect("SimpleLogger::clone() failed because the logger was poisoned").clone()),
+            stamping: self.stamping,
+        }
     }
 }
 
@@ -24,6 +32,7 @@ impl<S: Sink + Clone> Clone for SimpleLogger<S> {
 pub struct ChannelLogger<'a, S: Sink> {
     id: usize,
     sink: &'a Mutex<S>,
+    stamping: &'a Stamping,
 }
 
 impl<S: Sink> Copy for ChannelLogger<'_, S> {}
@@ -37,19 +46,32 @@ impl<S: Sink> SimpleLogger<S> {
     /// Constructs a new [SimpleLogger].
     #[must_use]
     pub const fn new(sink: S) -> Self {
-        Self { sink: Mutex::new(sink) }
+        Self { sink: Mutex::new(sink), stamping: Stamping::new() }
+    }
+
+    /// Sets the [Stamping] of this logger (and its [ChannelLogger]s).
+    #[must_use]
+    pub fn with_stamping(mut self, stamping: Stamping) -> Self {
+        self.stamping = stamping;
+        self
+    }
+
+    /// Returns the [Stamping] of this logger (and its [ChannelLogger]s).
+    #[must_use]
+    pub const fn stamping(&self) -> &Stamping {
+        &self.stamping
     }
 
     /// Constructs a new [ChannelLogger] to this logger's [Sink].
     #[must_use]
-    pub const fn channel(&self, channel_id: usize) -> ChannelLogger<S> {
-        ChannelLogger { id: channel_id, sink: &self.sink }
+    pub const fn channel(&self, channel_id: usize) -> ChannelLogger<'_, S> {
+        ChannelLogger { id: channel_id, sink: &self.sink, stamping: &self.stamping }
     }
 
     /// Grants access to underlying [Sink].
     ///
     /// See [Mutex::lock()].
-	#[must_use]
+    #[must_use = "the sink is only accessible through the returned guard"]
     pub fn sink(&self) -> LockResult<MutexGuard<'_, S>> {
         self.sink.lock()
     }
@@ -57,7 +79,7 @@ impl<S: Sink> SimpleLogger<S> {
     /// Consumes this logger, returning the underlying [Sink].
     ///
     /// See [Mutex::into_inner()].
-    #[must_use]
+    #[must_use = "the sink is dropped if it is not used"]
     pub fn into_sink(self) -> LockResult<S> {
         self.sink.into_inner()
     }
@@ -73,21 +95,176 @@ impl<S: Sink> ChannelLogger<'_, S> {
     /// Grants access to underlying [Sink].
     ///
     /// See [Mutex::lock()].
-    #[must_use]
+    #[must_use = "the sink is only accessible through the returned guard"]
     pub fn sink(&self) -> LockResult<MutexGuard<'_, S>> {
         self.sink.lock()
     }
 }
 
 impl<S: Sink> Logger for SimpleLogger<S> {
+    #[track_caller]
     fn log(&self, severity: Level, message: Arguments) {
-        self.sink().expect("SimpleLogger::log() failed because the logger was poisoned").consume(LogObject::new(0, severity, message))
+        let context = context::current();
+        self.sink().expect("SimpleLogger::log() failed because the logger was poisoned").consume(self.stamping.log_object(0, severity, message, context.as_context()))
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        let context = context::current();
+        let log_object = LogObject { location, ..self.stamping.log_object(0, severity, message, context.as_context()) };
+        self.sink().expect("SimpleLogger::log_at() failed because the logger was poisoned").consume(log_object)
+    }
+
+    fn flush(&self) {
+        self.sink().expect("SimpleLogger::flush() failed because the logger was poisoned").flush()
     }
 }
 
 impl<S: Sink> Logger for ChannelLogger<'_, S> {
+    #[track_caller]
+    fn log(&self, severity: Level, message: Arguments) {
+        let context = context::current();
+        self.sink().expect("ChannelLogger::log() failed because the underlying logger was poisoned").consume(self.stamping.log_object(self.id, severity, message, context.as_context()))
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        let context = context::current();
+        let log_object = LogObject { location, ..self.stamping.log_object(self.id, severity, message, context.as_context()) };
+        self.sink().expect("ChannelLogger::log_at() failed because the underlying logger was poisoned").consume(log_object)
+    }
+
+    fn flush(&self) {
+        self.sink().expect("ChannelLogger::flush() failed because the underlying logger was poisoned").flush()
+    }
+}
+
+/// An [Arc]-based [SimpleLogger] whose [SharedChannelLogger]s own a reference to it.
+///
+/// Clones share the same [SimpleLogger].
+///
+/// ```
+/// # use logidize::{*, loggers::multi_threaded::SharedLogger, sinks::CaptureSink};
+/// let capture = CaptureSink::new();
+/// let logger = SharedLogger::new(capture.clone());
+/// let workers: Vec<_> = (1..=4).map(|id| {
+///     let channel = logger.channel(id);
+///     std::thread::spawn(move || info!(channel, "worker {id} started"))
+/// }).collect();
+/// workers.into_iter().for_each(|worker| worker.join().unwrap());
+/// assert_eq!(capture.len(), 4);
+/// ```
+#[derive(Debug, Default)]
+pub struct SharedLogger<S: Sink> {
+    logger: Arc<SimpleLogger<S>>,
+}
+
+impl<S: Sink> Clone for SharedLogger<S> {
+    fn clone(&self) -> Self {
+        Self { logger: Arc::clone(&self.logger) }
+    }
+}
+
+/// An owned version of [ChannelLogger] created with [SharedLogger::channel()].
+#[derive(Debug)]
+pub struct SharedChannelLogger<S: Sink> {
+    id: usize,
+    logger: Arc<SimpleLogger<S>>,
+}
+
+impl<S: Sink> Clone for SharedChannelLogger<S> {
+    fn clone(&self) -> Self {
+        Self { id: self.id, logger: Arc::clone(&self.logger) }
+    }
+}
+
+impl<S: Sink> SharedLogger<S> {
+    /// Constructs a new [SharedLogger].
+    #[must_use]
+    pub fn new(sink: S) -> Self {
+        SimpleLogger::new(sink).into()
+    }
+
+    /// Constructs a new [SharedChannelLogger] to this logger's [Sink].
+    #[must_use]
+    pub fn channel(&self, channel_id: usize) -> SharedChannelLogger<S> {
+        SharedChannelLogger { id: channel_id, logger: Arc::clone(&self.logger) }
+    }
+
+    /// Returns the shared [SimpleLogger].
+    #[must_use]
+    pub fn logger(&self) -> &SimpleLogger<S> {
+        &self.logger
+    }
+
+    /// Grants access to underlying [Sink].
+    ///
+    /// See [Mutex::lock()].
+    pub fn sink(&self) -> LockResult<MutexGuard<'_, S>> {
+        self.logger.sink()
+    }
+
+    /// Returns the shared [SimpleLogger] if this is its last [SharedLogger] or [SharedChannelLogger].
+    ///
+    /// See [Arc::into_inner()].
+    #[must_use]
+    pub fn into_logger(self) -> Option<SimpleLogger<S>> {
+        Arc::into_inner(self.logger)
+    }
+}
+
+impl<S: Sink> From<SimpleLogger<S>> for SharedLogger<S> {
+    fn from(logger: SimpleLogger<S>) -> Self {
+        Self { logger: Arc::new(logger) }
+    }
+}
+
+impl<S: Sink> SharedChannelLogger<S> {
+    /// Returns ID of the channel this logger logs to.
+    #[must_use]
+    pub const fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns a [ChannelLogger] to the same channel borrowing the shared [SimpleLogger].
+    #[must_use]
+    pub fn borrow(&self) -> ChannelLogger<'_, S> {
+        self.logger.channel(self.id)
+    }
+
+    /// Grants access to underlying [Sink].
+    ///
+    /// See [Mutex::lock()].
+    pub fn sink(&self) -> LockResult<MutexGuard<'_, S>> {
+        self.logger.sink()
+    }
+}
+
+impl<S: Sink> Logger for SharedLogger<S> {
+    #[track_caller]
+    fn log(&self, severity: Level, message: Arguments) {
+        self.logger.log(severity, message)
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        self.logger.log_at(location, severity, message)
+    }
+
+    fn flush(&self) {
+        self.logger.flush()
+    }
+}
+
+impl<S: Sink> Logger for SharedChannelLogger<S> {
+    #[track_caller]
     fn log(&self, severity: Level, message: Arguments) {
-        self.sink().expect("ChannelLogger::log() failed because the underlying logger was poisoned").consume(LogObject::new(self.id, severity, message))
+        self.borrow().log(severity, message)
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        self.borrow().log_at(location, severity, message)
+    }
+
+    fn flush(&self) {
+        self.borrow().flush()
     }
 }
 
@@ -137,4 +314,28 @@ mod tests {
             [10_000, 20_000, 30_000, 40_000, 50_000, 60_000, 70_000, 80_000, 90_000, 100_000],
         );
     }
+
+    #[test]
+    fn test_shared() {
+        fn assert_owned<T: Clone + Send + Sync + 'static>(_: &T) {}
+        let capture = crate::sinks::CaptureSink::new();
+        let logger = SharedLogger::new(capture.clone());
+        let workers: Vec<_> = (0..4).map(|i| {
+            let channel = logger.channel(i);
+            assert_owned(&channel);
+            std::thread::spawn(move || {
+                for _ in 0..1_000 {
+                    debug!(channel, "message");
+                }
+            })
+        }).collect();
+        assert_owned(&logger);
+        workers.into_iter().for_each(|worker| worker.join().unwrap());
+        let line = line!() + 1;
+        debug!(logger.clone(), "done");
+        assert_eq!(capture.len(), 4 * 1_000 + 1);
+        assert_eq!(capture.on_channel(3).len(), 1_000);
+        assert_eq!(capture.take().last().unwrap().location.line(), line);
+        assert!(logger.into_logger().is_some());
+    }
 }
@@ -1,9 +1,10 @@
 //! [Logger]s for use in a single-threaded context.
 
-use std::{marker::PhantomData, cell::Cell};
+use std::{marker::PhantomData, cell::Cell, panic::Location};
 
 use crate::{
-    loggers::{Arguments, Level, Logger, LogObject},
+    context,
+    loggers::{Arguments, Level, LogObject, Logger, Stamping},
     sinks::Sink,
 };
 
@@ -15,6 +16,7 @@ use crate::{
 #[derive(Clone, Copy, Debug, Default)]
 pub struct SimpleLogger<S: Sink> {
     sink: S,
+    stamping: Stamping,
     _unsync: PhantomData<Cell<()>>,
 }
 
@@ -28,6 +30,7 @@ pub struct SimpleLogger<S: Sink> {
 pub struct ChannelLogger<'a, S: Sink> {
     channel_id: usize,
     sink: &'a S,
+    stamping: &'a Stamping,
     _unsendsync: PhantomData<*const ()>,
 }
 
@@ -42,19 +45,33 @@ impl<S: Sink> SimpleLogger<S> {
     /// Constructs a new [SimpleLogger].
     #[must_use]
     pub const fn new(sink: S) -> Self {
-        Self { sink, _unsync: PhantomData }
+        Self { sink, stamping: Stamping::new(), _unsync: PhantomData }
+    }
+
+    /// Sets the [Stamping] of this logger (and its [ChannelLogger]s).
+    #[must_use]
+    pub fn with_stamping(mut self, stamping: Stamping) -> Self {
+        self.stamping = stamping;
+        self
+    }
+
+    /// Returns the [Stamping] of this logger (and its [ChannelLogger]s).
+    #[must_use]
+    pub const fn stamping(&self) -> &Stamping {
+        &self.stamping
     }
 
     /// Constructs a new [ChannelLogger] to this logger's [Sink].
     #[must_use]
-    pub const fn channel(&self, channel_id: usize) -> ChannelLogger<S> {
-        ChannelLogger { channel_id, sink: &self.sink, _unsendsync: PhantomData }
+    pub const fn channel(&self, channel_id: usize) -> ChannelLogger<'_, S> {
+        ChannelLogger { channel_id, sink: &self.sink, stamping: &self.stamping, _unsendsync: PhantomData }
     }
 
     /// Grants access to underlying [Sink].
     ///
     /// This is safe due to the threading limitations on [SimpleLogger] and [ChannelLogger].
     #[must_use]
+    #[allow(clippy::mut_from_ref, invalid_reference_casting)]
     pub fn sink(&self) -> &mut S {
         let ptr: *const S = &self.sink;
         let ptr = ptr as *mut S;
@@ -79,6 +96,7 @@ impl<S: Sink> ChannelLogger<'_, S> {
     ///
     /// This is safe due to the threading limitations on [SimpleLogger] and [ChannelLogger].
     #[must_use]
+    #[allow(clippy::mut_from_ref, invalid_reference_casting)]
     pub fn sink(&self) -> &mut S {
         let ptr: *const S = self.sink;
         let ptr = ptr as *mut S;
@@ -87,14 +105,36 @@ impl<S: Sink> ChannelLogger<'_, S> {
 }
 
 impl<S: Sink> Logger for SimpleLogger<S> {
+    #[track_caller]
     fn log(&self, severity: Level, message: Arguments) {
-        self.sink().consume(LogObject::new(0, severity, message))
+        let context = context::current();
+        self.sink().consume(self.stamping.log_object(0, severity, message, context.as_context()))
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        let context = context::current();
+        self.sink().consume(LogObject { location, ..self.stamping.log_object(0, severity, message, context.as_context()) })
+    }
+
+    fn flush(&self) {
+        self.sink().flush()
     }
 }
 
 impl<S: Sink> Logger for ChannelLogger<'_, S> {
+    #[track_caller]
     fn log(&self, severity: Level, message: Arguments) {
-        self.sink().consume(LogObject::new(self.channel_id, severity, message))
+        let context = context::current();
+        self.sink().consume(self.stamping.log_object(self.channel_id, severity, message, context.as_context()))
+    }
+
+    fn log_at(&self, location: &'static Location<'static>, severity: Level, message: Arguments) {
+        let context = context::current();
+        self.sink().consume(LogObject { location, ..self.stamping.log_object(self.channel_id, severity, message, context.as_context()) })
+    }
+
+    fn flush(&self) {
+        self.sink().flush()
     }
 }
 
@@ -138,4 +178,18 @@ mod tests {
             debug!(channel, "message");
         }
     }
+
+    #[test]
+    fn test_location() {
+        let mut lines = Vec::new();
+        let logger = SimpleLogger::new(|log_object: LogObject| {
+            assert_eq!(log_object.location.file(), file!());
+            lines.push(log_object.location.line());
+        });
+        let first_line = line!() + 1;
+        debug!(logger, "message");
+        log!(logger.channel(1), Level::DEBUG, "message");
+        let _ = logger.into_sink();
+        assert_eq!(lines, [first_line, first_line + 1]);
+    }
 }
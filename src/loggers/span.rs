@@ -0,0 +1,171 @@
+//! [Span] for logging the entry and exit of timed scopes.
+
+use std::{
+    cell::Cell,
+    fmt::Arguments,
+    marker::PhantomData,
+    panic::Location,
+    time::{Duration, Instant},
+};
+
+use crate::loggers::{Level, Logger};
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Logs `start {name}` when entered and `end {name} took {elapsed:?}` when dropped.
+///
+/// Messages are indented by two spaces per enclosing [Span] on the same thread.
+/// If [Span::slow_threshold] is set and exceeded, the exit is logged with at least [Level::WARNING].
+/// Both the entry and the exit are logged with the [LogObject::location](super::LogObject::location) of the span's creation
+/// (see [Logger::log_at()]).
+///
+/// Usually created with [timed!](crate::timed!).
+/// [ChannelLogger](super::single_threaded::ChannelLogger)s have to outlive the [Span], so bind them to a variable first.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use logidize::{*, loggers::{Level, multi_threaded::SimpleLogger}, sinks::CaptureSink};
+/// let capture = CaptureSink::new();
+/// let logger = SimpleLogger::new(capture.clone());
+/// let assets = logger.channel(1);
+/// {
+///     let _load = timed!(assets, Level::INFO, "load {}", "assets").with_slow_threshold(Duration::from_secs(10));
+///     let _decode = timed!(assets, Level::DEBUG, "decode");
+/// }
+/// let messages: Vec<_> = capture.records().into_iter().map(|record| record.message).collect();
+/// assert_eq!(messages[0], "start load assets");
+/// assert_eq!(messages[1], "  start decode");
+/// assert!(messages[2].starts_with("  end decode took "));
+/// assert!(messages[3].starts_with("end load assets took "));
+/// ```
+#[derive(Debug)]
+#[must_use = "the span ends immediately if it is not kept alive"]
+pub struct Span<'a, L: Logger + ?Sized> {
+    /// The severity level of the entry and (unless slow) exit.
+    pub severity: Level,
+    /// The duration after which the exit is logged with at least [Level::WARNING] (if set).
+    pub slow_threshold: Option<Duration>,
+    depth: usize,
+    location: &'static Location<'static>,
+    logger: &'a L,
+    name: String,
+    start: Instant,
+    _unsend: PhantomData<*const ()>,
+}
+
+impl<'a, L: Logger + ?Sized> Span<'a, L> {
+    /// Logs the entry of a [Span] named `name` and starts timing it.
+    #[track_caller]
+    pub fn enter(logger: &'a L, severity: Level, name: Arguments) -> Self {
+        let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+        let name = name.to_string();
+        logger.log(severity, format_args!("{:indent$}start {name}", "", indent = 2 * depth));
+        let location = Location::caller();
+        Self { severity, slow_threshold: None, depth, location, logger, name, start: Instant::now(), _unsend: PhantomData }
+    }
+
+    /// Sets [Span::slow_threshold].
+    pub fn with_slow_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Returns the name of this span.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of [Span]s enclosing this one on the same thread.
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the time elapsed since this span was entered.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl<L: Logger + ?Sized> Drop for Span<'_, L> {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        let _ = DEPTH.try_with(|depth| depth.set(self.depth));
+        let slow = self.slow_threshold.is_some_and(|threshold| elapsed >= threshold);
+        let severity = match slow {
+            true => self.severity.max(Level::WARNING),
+            false => self.severity,
+        };
+        self.logger.log_at(self.location, severity, format_args!(
+            "{:indent$}end {} took {elapsed:?}{}",
+            "",
+            self.name,
+            if slow { " (slow)" } else { "" },
+            indent = 2 * self.depth,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        timed,
+        loggers::{multi_threaded, single_threaded},
+        sinks::CaptureSink,
+    };
+
+    #[test]
+    fn test_nesting() {
+        let capture = CaptureSink::new();
+        let logger = single_threaded::SimpleLogger::new(capture.clone());
+        let channel = logger.channel(2);
+        {
+            let outer = timed!(logger, Level::INFO, "outer {}", 1);
+            assert_eq!(outer.depth(), 0);
+            {
+                let inner = timed!(channel, Level::DEBUG, "inner");
+                assert_eq!((inner.depth(), inner.name()), (1, "inner"));
+            }
+            let _sibling = timed!(channel, Level::DEBUG, "sibling");
+        }
+        let records = capture.take();
+        let messages: Vec<_> = records.iter().map(|record| record.message.split(" took ").next().unwrap()).collect();
+        assert_eq!(messages, ["start outer 1", "  start inner", "  end inner", "  start sibling", "  end sibling", "end outer 1"]);
+        assert_eq!(records.iter().map(|record| record.channel_id).collect::<Vec<_>>(), [0, 2, 2, 2, 2, 0]);
+        assert_eq!(records[5].severity, Level::INFO);
+    }
+
+    #[test]
+    fn test_location() {
+        let capture = CaptureSink::new();
+        let logger = multi_threaded::SimpleLogger::new(capture.clone());
+        let line = line!() + 1;
+        drop(timed!(logger, Level::INFO, "located"));
+        let records = capture.take();
+        assert!(records.iter().all(|record| record.location.file() == file!() && record.location.line() == line));
+    }
+
+    #[test]
+    fn test_slow_threshold() {
+        let capture = CaptureSink::new();
+        let logger = multi_threaded::SimpleLogger::new(capture.clone());
+        let channel = logger.channel(1);
+        drop(timed!(channel, Level::DEBUG, "fast").with_slow_threshold(Duration::from_secs(60)));
+        {
+            let _slow = timed!(logger, Level::DEBUG, "slow").with_slow_threshold(Duration::from_millis(1));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        drop(timed!(logger, Level::ERROR, "already severe").with_slow_threshold(Duration::ZERO));
+        let records = capture.take();
+        assert_eq!(records[1].severity, Level::DEBUG);
+        assert!(!records[1].message.ends_with("(slow)"));
+        assert_eq!(records[3].severity, Level::WARNING);
+        assert!(records[3].message.starts_with("end slow took ") && records[3].message.ends_with(" (slow)"));
+        assert_eq!(records[5].severity, Level::ERROR);
+    }
+}
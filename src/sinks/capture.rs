@@ -0,0 +1,170 @@
+//! [CaptureSink] for inspecting logged [LogObject]s (e.g. in tests).
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    loggers::{Level, LogObject, LogRecord},
+    sinks::Sink,
+};
+
+/// A [Sink] that stores [LogRecord]s of all [LogObject]s in a buffer shared by all of its clones.
+///
+/// Keep a clone to inspect the records after passing the sink to a logger (which may hide it behind a [Mutex]).
+///
+/// ```
+/// # use logidize::{*, loggers::{Level, multi_threaded::SimpleLogger}, sinks::CaptureSink};
+/// let capture = CaptureSink::new();
+/// let logger = SimpleLogger::new(capture.clone());
+/// warning!(logger.channel(3), "timeout after {}s", 5);
+/// assert_logged!(capture, Level::WARNING, "timeout");
+/// assert_eq!(capture.on_channel(3).len(), 1);
+/// assert!(capture.records_at(Level::ERROR).is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CaptureSink {
+    records: Arc<Mutex<Vec<LogRecord>>>,
+}
+
+impl CaptureSink {
+    /// Constructs a new [CaptureSink] with an empty buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the locked buffer of captured [LogRecord]s.
+    pub fn lock(&self) -> MutexGuard<'_, Vec<LogRecord>> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns all captured [LogRecord]s.
+    #[must_use]
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.lock().clone()
+    }
+
+    /// Returns the captured [LogRecord]s of the severity level.
+    #[must_use]
+    pub fn records_at(&self, severity: Level) -> Vec<LogRecord> {
+        self.filtered(|record| record.severity == severity)
+    }
+
+    /// Returns the captured [LogRecord]s of the channel.
+    #[must_use]
+    pub fn on_channel(&self, channel_id: usize) -> Vec<LogRecord> {
+        self.filtered(|record| record.channel_id == channel_id)
+    }
+
+    /// Returns whether a captured [LogRecord]'s message contains `text`.
+    #[must_use]
+    pub fn contains_message(&self, text: &str) -> bool {
+        self.lock().iter().any(|record| record.message.contains(text))
+    }
+
+    /// Returns the captured [LogRecord]s matching the predicate.
+    #[must_use]
+    pub fn filtered(&self, mut predicate: impl FnMut(&LogRecord) -> bool) -> Vec<LogRecord> {
+        self.lock().iter().filter(|record| predicate(record)).cloned().collect()
+    }
+
+    /// Returns the number of captured [LogRecord]s.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no [LogRecord]s have been captured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes and returns all captured [LogRecord]s.
+    pub fn take(&self) -> Vec<LogRecord> {
+        std::mem::take(&mut *self.lock())
+    }
+}
+
+impl Sink for CaptureSink {
+    fn consume(&mut self, log_object: LogObject) {
+        self.lock().push(log_object.to_record());
+    }
+}
+
+/// Asserts that a [CaptureSink] captured a [LogRecord](crate::loggers::LogRecord) whose message contains the text,
+/// optionally with the given severity [Level](crate::loggers::Level).
+///
+/// On failure, all captured records are listed.
+///
+/// ```
+/// # use logidize::{*, loggers::{Level, single_threaded::SimpleLogger}, sinks::CaptureSink};
+/// let capture = CaptureSink::new();
+/// let logger = SimpleLogger::new(capture.clone());
+/// error!(logger, "connection refused");
+/// assert_logged!(capture, "refused");
+/// assert_logged!(capture, Level::ERROR, "connection");
+/// ```
+#[macro_export]
+macro_rules! assert_logged {
+    (@assert $capture:expr, $lvl:expr, $text:expr) => {{
+        let capture: &$crate::sinks::CaptureSink = &$capture;
+        let severity: Option<$crate::loggers::Level> = $lvl;
+        let text: &str = $text;
+        let records = capture.lock();
+        if !records.iter().any(|record| severity.map_or(true, |severity| record.severity == severity) && record.message.contains(text)) {
+            let mut message = match severity {
+                Some(severity) => format!("no {severity} record containing {text:?} was logged"),
+                None => format!("no record containing {text:?} was logged"),
+            };
+            message.push_str("; captured records:");
+            for record in records.iter() {
+                message.push_str(&format!("\n  [{}][{}] {}", record.severity, record.channel_id, record.message));
+            }
+            panic!("{}", message);
+        }
+    }};
+
+    ($capture:expr, $text:expr $(,)?) => {
+        $crate::assert_logged!(@assert $capture, None, $text)
+    };
+
+    ($capture:expr, $lvl:expr, $text:expr $(,)?) => {
+        $crate::assert_logged!(@assert $capture, Some($lvl), $text)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        debug, error, info,
+        loggers::{Logger, multi_threaded::SimpleLogger},
+    };
+
+    #[test]
+    fn test_capture() {
+        let capture = CaptureSink::new();
+        let logger = SimpleLogger::new(capture.clone());
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let logger = &logger;
+                scope.spawn(move || info!(logger.channel(i), "from thread {i}"));
+            }
+        });
+        debug!(logger, "debug");
+        error!(logger.channel(2), "error");
+        assert_eq!(capture.len(), 6);
+        assert_eq!(capture.records_at(Level::INFO).len(), 4);
+        assert_eq!(capture.on_channel(2).len(), 2);
+        assert!(capture.contains_message("thread 3"));
+        assert!(!capture.contains_message("thread 4"));
+        crate::assert_logged!(capture, Level::ERROR, "error");
+        crate::assert_logged!(capture, "from thread");
+        let panic = std::panic::catch_unwind(|| crate::assert_logged!(capture, Level::WARNING, "error")).unwrap_err();
+        let panic = panic.downcast_ref::<String>().unwrap();
+        assert!(panic.starts_with("no WARNING record containing \"error\" was logged; captured records:\n"));
+        assert!(panic.contains("\n  [ERROR][2] error"));
+        assert_eq!(capture.take().len(), 6);
+        assert!(capture.is_empty());
+    }
+}
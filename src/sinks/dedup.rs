@@ -0,0 +1,248 @@
+//! [DedupSink] for collapsing repeated [LogObject]s.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    loggers::{LogObject, LogRecord},
+    sinks::{Sink, SinkError, forward},
+};
+
+#[derive(Clone, Debug)]
+struct Streak {
+    /// The last [LogObject] of the streak.
+    record: LogRecord,
+    repeats: u64,
+    since: Instant,
+}
+
+impl Streak {
+    /// Summarizes the repeats with the time and thread of `trigger` (or of the last repeat without one).
+    fn summarize(&mut self, sink: &mut impl Sink, now: Instant, trigger: Option<&LogObject>, fallible: bool) -> Result<(), SinkError> {
+        if self.repeats == 0 {
+            return Ok(());
+        }
+        let repeats = std::mem::take(&mut self.repeats);
+        self.since = now;
+        self.record.with_log_object(|log_object| {
+            let trigger = trigger.unwrap_or(&log_object);
+            forward(sink, LogObject {
+                message: format_args!("last message repeated {repeats} time{}: {}", if repeats == 1 { "" } else { "s" }, log_object.message),
+                thread: trigger.thread,
+                thread_id: trigger.thread_id,
+                time: trigger.time,
+                ..log_object
+            }, fallible)
+        })
+    }
+}
+
+/// A [Sink] that passes [LogObject]s to another [Sink] unless they repeat a recent [LogObject].
+///
+/// [LogObject]s are considered equal if their channel, severity and rendered message are.
+/// The last [DedupSink::history] distinct [LogObject]s are remembered.
+/// Repeats are counted and summarized with the original severity when the repeated [LogObject] is forgotten,
+/// when [DedupSink::timeout] has passed since the start of the streak (checked whenever the [DedupSink] is used)
+/// or when the [DedupSink] is flushed or dropped.
+/// Other [LogObject]s are passed on immediately.
+#[derive(Clone, Debug)]
+pub struct DedupSink<S: Sink> {
+    /// The number of distinct [LogObject]s that are remembered (at least `1`).
+    pub history: usize,
+    /// The underlying [Sink].
+    pub sink: S,
+    /// The maximum duration a streak of repeats is collapsed before it is summarized.
+    pub timeout: Duration,
+    streaks: VecDeque<Streak>,
+}
+
+impl<S: Sink> DedupSink<S> {
+    /// Constructs a new [DedupSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(sink: S) -> Self {
+        Self {
+            history: 1,
+            sink,
+            timeout: Duration::from_secs(30),
+            streaks: VecDeque::new(),
+        }
+    }
+
+    /// Summarizes all pending repeats.
+    fn summarize_all(&mut self, now: Instant) {
+        for streak in &mut self.streaks {
+            let _ = streak.summarize(&mut self.sink, now, None, false);
+        }
+    }
+
+    /// Passes `log_object` on unless it repeats a recent [LogObject] (see [forward()]).
+    fn dedup(&mut self, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+        let now = Instant::now();
+        let record = log_object.to_record();
+        let mut result = Ok(());
+        for streak in &mut self.streaks {
+            if now.duration_since(streak.since) >= self.timeout {
+                result = result.and(streak.summarize(&mut self.sink, now, Some(&log_object), fallible));
+            }
+        }
+        let repeated = self.streaks.iter().position(|streak| {
+            streak.record.channel_id == record.channel_id
+                && streak.record.severity == record.severity
+                && streak.record.message == record.message
+        });
+        if let Some(i) = repeated {
+            let mut streak = self.streaks.remove(i).unwrap();
+            if streak.repeats == 0 {
+                streak.since = now;
+            }
+            streak.repeats += 1;
+            streak.record = record;
+            self.streaks.push_front(streak);
+            return result;
+        }
+        while self.streaks.len() >= self.history.max(1) {
+            let mut streak = self.streaks.pop_back().unwrap();
+            result = result.and(streak.summarize(&mut self.sink, now, Some(&log_object), fallible));
+        }
+        self.streaks.push_front(Streak { record, repeats: 0, since: now });
+        forward(&mut self.sink, log_object, fallible).and(result)
+    }
+}
+
+impl<S: Sink> Sink for DedupSink<S> {
+    fn consume(&mut self, log_object: LogObject) {
+        let _ = self.dedup(log_object, false);
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.dedup(log_object, true)
+    }
+
+    fn flush(&mut self) {
+        self.summarize_all(Instant::now());
+        self.sink.flush();
+    }
+}
+
+impl<S: Sink> Drop for DedupSink<S> {
+    fn drop(&mut self) {
+        self.summarize_all(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{
+        clock::{Clock, ManualClock},
+        filter_maps::InvisibleChannelFilterMap,
+        info, warning,
+        loggers::{Level, Logger, Stamping, single_threaded::SimpleLogger},
+        sinks::{CaptureSink, ErrorPolicy, WriteSink},
+    };
+
+    fn collect(messages: &mut Vec<(Level, String)>) -> impl FnMut(LogObject) + '_ {
+        |log_object: LogObject| messages.push((log_object.severity, log_object.message.to_string()))
+    }
+
+    fn as_strs(messages: &[(Level, String)]) -> Vec<(Level, &str)> {
+        messages.iter().map(|(severity, message)| (*severity, message.as_str())).collect()
+    }
+
+    #[test]
+    fn test_streak() {
+        let mut messages = Vec::new();
+        let logger = SimpleLogger::new(DedupSink::new(collect(&mut messages)));
+        for _ in 0..3 {
+            info!(logger, "repeated {}", 1);
+        }
+        warning!(logger, "repeated {}", 1);
+        info!(logger, "once");
+        info!(logger.channel(1), "once");
+        info!(logger.channel(1), "once");
+        logger.flush();
+        drop(logger);
+        assert_eq!(as_strs(&messages), [
+            (Level::INFO, "repeated 1"),
+            (Level::INFO, "last message repeated 2 times: repeated 1"),
+            (Level::WARNING, "repeated 1"),
+            (Level::INFO, "once"),
+            (Level::INFO, "once"),
+            (Level::INFO, "last message repeated 1 time: once"),
+        ]);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut messages = Vec::new();
+        let mut sink = DedupSink::new(collect(&mut messages));
+        sink.history = 2;
+        let logger = SimpleLogger::new(sink);
+        for _ in 0..3 {
+            info!(logger, "a");
+            info!(logger, "b");
+        }
+        info!(logger, "c");
+        info!(logger, "b");
+        drop(logger);
+        assert_eq!(as_strs(&messages), [
+            (Level::INFO, "a"),
+            (Level::INFO, "b"),
+            (Level::INFO, "last message repeated 2 times: a"),
+            (Level::INFO, "c"),
+            (Level::INFO, "last message repeated 3 times: b"),
+        ]);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut messages = Vec::new();
+        let mut sink = DedupSink::new(collect(&mut messages));
+        sink.timeout = Duration::ZERO;
+        let logger = SimpleLogger::new(sink);
+        for _ in 0..3 {
+            info!(logger, "a");
+        }
+        drop(logger);
+        assert_eq!(as_strs(&messages), [
+            (Level::INFO, "a"),
+            (Level::INFO, "last message repeated 1 time: a"),
+            (Level::INFO, "last message repeated 1 time: a"),
+        ]);
+    }
+
+    #[test]
+    fn test_summary_time() {
+        static CLOCK: ManualClock = ManualClock::new(UNIX_EPOCH);
+        let mut times = Vec::new();
+        let sink = DedupSink::new(|log_object: LogObject| times.push(log_object.time.duration_since(UNIX_EPOCH).unwrap().as_secs()));
+        let logger = SimpleLogger::new(sink).with_stamping(Stamping { clock: Clock::Manual(&CLOCK), thread: None });
+        for message in ["a", "a", "b", "b"] {
+            CLOCK.advance(Duration::from_secs(1));
+            info!(logger, "{message}");
+        }
+        CLOCK.advance(Duration::from_secs(1));
+        drop(logger);
+        assert_eq!(times, [1, 3, 3, 4]);
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let fallback = CaptureSink::new();
+        let mut output = WriteSink::new(std::io::Cursor::new([0; 0]), InvisibleChannelFilterMap);
+        output.error_policy = ErrorPolicy::fallback(fallback.clone());
+        let logger = SimpleLogger::new(DedupSink::new(output));
+        info!(logger, "a");
+        info!(logger, "a");
+        logger.flush();
+        assert_eq!(logger.sink().sink.failed_writes, 2);
+        assert_eq!(
+            fallback.take().iter().map(|record| record.message.as_str()).collect::<Vec<_>>(),
+            ["a", "last message repeated 1 time: a"],
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! [FailoverSink] for switching to a secondary [Sink] while the primary fails.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    loggers::{Level, LogObject},
+    sinks::{Sink, SinkError, forward, synthesize},
+};
+
+/// A [Sink] that passes [LogObject]s to a primary [Sink] and fails over to a secondary [Sink] on error.
+///
+/// While failed over, the primary is retried with exponential backoff (from [FailoverSink::initial_backoff] up to [FailoverSink::max_backoff]).
+/// A [Level::WARNING] marker is logged on the channel of the triggering [LogObject] whenever the sinks are switched:
+/// to the secondary when switching away and to the primary when switching back, which also serves as the retry.
+/// If the marker can't be written to the secondary, the [LogObject] is still passed to it.
+#[derive(Clone, Debug)]
+pub struct FailoverSink<P: Sink, S: Sink> {
+    /// The backoff before the first retry of [FailoverSink::primary] after it failed.
+    pub initial_backoff: Duration,
+    /// The maximum backoff between retries of [FailoverSink::primary].
+    pub max_backoff: Duration,
+    /// The [Sink] used while it doesn't fail.
+    pub primary: P,
+    /// The [Sink] used while [FailoverSink::primary] fails.
+    pub secondary: S,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl<P: Sink, S: Sink> FailoverSink<P, S> {
+    /// Constructs a new [FailoverSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(primary: P, secondary: S) -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            primary,
+            secondary,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Returns whether [LogObject]s are currently passed to [FailoverSink::secondary].
+    #[must_use]
+    pub const fn is_failed_over(&self) -> bool {
+        self.retry_at.is_some()
+    }
+
+    fn fail_over(&mut self, error: SinkError, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+        let backoff = self.initial_backoff.saturating_mul(1 << self.failures.min(31));
+        self.retry_at = Some(Instant::now() + backoff.min(self.max_backoff));
+        self.failures += 1;
+        let _ = forward(&mut self.secondary, synthesize(
+            Some(&log_object),
+            log_object.channel_id,
+            Level::WARNING,
+            format_args!("switching to secondary sink because primary sink failed: {error}"),
+        ), fallible);
+        forward(&mut self.secondary, log_object, fallible)
+    }
+
+    fn retry(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.primary.try_consume(synthesize(
+            Some(&log_object),
+            log_object.channel_id,
+            Level::WARNING,
+            format_args!("switching back to primary sink"),
+        ))?;
+        self.retry_at = None;
+        self.failures = 0;
+        self.primary.try_consume(log_object)
+    }
+
+    /// Passes `log_object` to [FailoverSink::primary] or (see [forward()]) [FailoverSink::secondary].
+    fn route(&mut self, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+        let result = match self.retry_at {
+            None => self.primary.try_consume(log_object),
+            Some(retry_at) if Instant::now() >= retry_at => self.retry(log_object),
+            Some(_) => return forward(&mut self.secondary, log_object, fallible),
+        };
+        match result {
+            Err(error) => self.fail_over(error, log_object, fallible),
+            ok => ok,
+        }
+    }
+}
+
+impl<P: Sink, S: Sink> Sink for FailoverSink<P, S> {
+    fn consume(&mut self, log_object: LogObject) {
+        let _ = self.route(log_object, false);
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.route(log_object, true)
+    }
+
+    fn flush(&mut self) {
+        self.primary.flush();
+        self.secondary.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        colors::Theme,
+        filter_maps::InvisibleChannelFilterMap,
+        info,
+        loggers::{Logger, single_threaded::SimpleLogger},
+        sinks::{CaptureSink, ErrorPolicy, WriteSink},
+        writers::Write,
+    };
+
+    #[derive(Debug, Default)]
+    struct FlakyWriter {
+        failing: bool,
+        output: Vec<u8>,
+    }
+
+    impl Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.failing {
+                true => Err(std::io::ErrorKind::BrokenPipe.into()),
+                false => self.output.write(buf),
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn messages(output: &[u8]) -> Vec<&str> {
+        std::str::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| line.split_once("]: ").unwrap().1)
+            .collect()
+    }
+
+    #[test]
+    fn test_failover() {
+        let mut primary = WriteSink::from_write(FlakyWriter::default(), InvisibleChannelFilterMap);
+        let mut secondary: WriteSink<Vec<u8>> = Default::default();
+        primary.theme = Theme::PLAIN;
+        secondary.theme = Theme::PLAIN;
+        let logger = SimpleLogger::new(FailoverSink::new(primary, secondary));
+        logger.sink().initial_backoff = Duration::ZERO;
+        info!(logger, "1");
+        logger.sink().primary.output.failing = true;
+        info!(logger, "2");
+        assert!(logger.sink().is_failed_over());
+        info!(logger, "3");
+        logger.sink().primary.output.failing = false;
+        info!(logger, "4");
+        assert!(!logger.sink().is_failed_over());
+        let sink = logger.into_sink();
+        assert_eq!(messages(&sink.primary.output.output), [
+            "1",
+            "switching back to primary sink",
+            "4",
+        ]);
+        assert_eq!(messages(&sink.secondary.output), [
+            "switching to secondary sink because primary sink failed: failed to write log: broken pipe",
+            "2",
+            "switching to secondary sink because primary sink failed: failed to write log: broken pipe",
+            "3",
+        ]);
+    }
+
+    /// A [Sink] capturing every [LogObject] but failing on [Level::WARNING] ones (like the switch markers).
+    #[derive(Debug, Default)]
+    struct FailingWarnings(CaptureSink);
+
+    impl Sink for FailingWarnings {
+        fn consume(&mut self, log_object: LogObject) {
+            let _ = self.try_consume(log_object);
+        }
+
+        fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+            self.0.consume(log_object);
+            match log_object.severity {
+                Level::WARNING => Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut primary = WriteSink::from_write(FlakyWriter::default(), InvisibleChannelFilterMap);
+        primary.output.failing = true;
+        let capture = CaptureSink::new();
+        let mut sink = FailoverSink::new(primary, FailingWarnings(capture.clone()));
+        sink.initial_backoff = Duration::from_secs(3600);
+        for i in 0..10 {
+            sink.consume(LogObject::new(0, Level::INFO, format_args!("{i}")));
+        }
+        assert!(sink.is_failed_over());
+        let messages: Vec<_> = capture.take().into_iter().map(|record| record.message).collect();
+        assert_eq!(messages[0], "switching to secondary sink because primary sink failed: failed to write log: broken pipe");
+        assert_eq!(messages[1..], ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let mut primary = WriteSink::from_write(FlakyWriter::default(), InvisibleChannelFilterMap);
+        primary.output.failing = true;
+        let fallback = CaptureSink::new();
+        let mut secondary = WriteSink::new(std::io::Cursor::new([0; 0]), InvisibleChannelFilterMap);
+        secondary.error_policy = ErrorPolicy::fallback(fallback.clone());
+        let logger = SimpleLogger::new(FailoverSink::new(primary, secondary));
+        info!(logger, "1");
+        info!(logger, "2");
+        assert_eq!(logger.sink().primary.failed_writes, 1);
+        assert_eq!(logger.sink().secondary.failed_writes, 3);
+        assert_eq!(fallback.take().iter().map(|record| record.message.as_str()).collect::<Vec<_>>(), [
+            "switching to secondary sink because primary sink failed: failed to write log: broken pipe",
+            "1",
+            "2",
+        ]);
+    }
+}
@@ -0,0 +1,299 @@
+//! [GelfSink] for sending [LogObject]s to Graylog.
+
+use std::{
+    fmt::Write as _,
+    io::{Error, ErrorKind},
+    net::{ToSocketAddrs, UdpSocket},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{Level, LogObject, ThreadFormat},
+    sinks::{ErrorPolicy, SamplingRng, Sink, SinkError, json, network, process, syslog_severity},
+};
+
+/// The magic bytes starting every GELF chunk.
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// The size of a GELF chunk's header.
+const CHUNK_HEADER_SIZE: usize = 12;
+/// The maximum number of chunks of a GELF message.
+const MAX_CHUNKS: usize = 128;
+
+/// How a [GelfSink] compresses messages.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum GelfCompression {
+    /// Messages are sent as plain JSON.
+    #[default]
+    None,
+    /// Messages are zlib-compressed.
+    ///
+    /// Requires the `zlib` feature, without it every send fails with [ErrorKind::Unsupported].
+    Zlib,
+}
+
+/// A [Sink] that sends [LogObject]s via a [ChannelFilterMap] as [GELF 1.1](https://go2docs.graylog.org/current/getting_in_log_data/gelf.html) over UDP.
+///
+/// The first line of a message is sent as `short_message` and multi-line messages are additionally sent as `full_message`.
+/// Besides `level`, every message carries the additional fields `_channel`, `_thread`, `_file` and `_line`.
+/// Every key of the [LogObject::context] becomes an additional field named like the key with characters other than
+/// letters, digits, `_`, `.` and `-` replaced by `_` (fields clashing with `_id` or the fields above are prefixed with `_context`,
+/// e.g. `_context_id` or `_context_channel`).
+/// Messages larger than [GelfSink::chunk_size] are split into chunks.
+#[derive(Debug)]
+pub struct GelfSink<M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// The [ChannelFilterMap] used for `_channel`.
+    pub channel_map: M,
+    /// The maximum size of a datagram (including the chunk header).
+    pub chunk_size: usize,
+    /// How messages are compressed.
+    pub compression: GelfCompression,
+    /// How failures of [GelfSink::socket] are handled by [Sink::consume()].
+    pub error_policy: ErrorPolicy,
+    /// The number of failed sends that weren't ignored according to [GelfSink::error_policy].
+    pub failed_writes: u64,
+    /// The `host`.
+    pub host: String,
+    /// The sink's minimum severity level. [GelfSink] won't log [LogObject]s of lower severity.
+    pub min_severity: Level,
+    /// Whether the sink is muted. A muted [GelfSink] won't log anything.
+    pub muted: bool,
+    /// The socket connected to the GELF input.
+    pub socket: UdpSocket,
+    /// How threads are displayed in `_thread`.
+    pub thread_format: ThreadFormat,
+    reported: bool,
+    rng: SamplingRng,
+}
+
+impl<M: ChannelFilterMap> GelfSink<M> {
+    /// Constructs a new [GelfSink] with default settings (that shouldn't be relied upon).
+    ///
+    /// The socket has to be connected to the GELF input (see [GelfSink::udp()]).
+    #[must_use]
+    pub fn new(socket: UdpSocket, channel_map: M) -> Self {
+        Self {
+            channel_map,
+            chunk_size: 1420,
+            compression: GelfCompression::None,
+            error_policy: ErrorPolicy::Count,
+            failed_writes: 0,
+            host: process::hostname().unwrap_or_else(|| "localhost".into()),
+            min_severity: Level::DEBUG,
+            muted: false,
+            socket,
+            thread_format: ThreadFormat::NameId,
+            reported: false,
+            rng: SamplingRng::from_random_seed(),
+        }
+    }
+
+    /// Constructs a new [GelfSink] sending to a GELF UDP input like `graylog:12201`.
+    pub fn udp(address: impl ToSocketAddrs, channel_map: M) -> std::io::Result<Self> {
+        network::connect_udp(address).map(|socket| Self::new(socket, channel_map))
+    }
+
+    fn send(&mut self, payload: &[u8]) -> std::io::Result<()> {
+        if payload.len() <= self.chunk_size {
+            return self.socket.send(payload).map(drop);
+        }
+        let chunk_payload_size = self.chunk_size.saturating_sub(CHUNK_HEADER_SIZE).max(1);
+        let count = payload.len().div_ceil(chunk_payload_size);
+        if count > MAX_CHUNKS {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("GELF message needs {count} chunks (at most {MAX_CHUNKS} are allowed)")));
+        }
+        let message_id = self.rng.next_u64().to_be_bytes();
+        let mut datagram = Vec::with_capacity(self.chunk_size);
+        for (i, chunk) in payload.chunks(chunk_payload_size).enumerate() {
+            datagram.clear();
+            datagram.extend_from_slice(&CHUNK_MAGIC);
+            datagram.extend_from_slice(&message_id);
+            datagram.extend_from_slice(&[i as u8, count as u8]);
+            datagram.extend_from_slice(chunk);
+            self.socket.send(&datagram)?;
+        }
+        Ok(())
+    }
+
+    /// Encodes and sends `log_object` unless it's filtered out.
+    fn write_record(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        if self.muted || log_object.severity < self.min_severity {
+            return Ok(());
+        }
+        let Some(channel_name) = self.channel_map.filter_map(&log_object) else {
+            return Ok(());
+        };
+        let message = log_object.message.to_string();
+        let timestamp = log_object.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut gelf = String::from("{\"version\":\"1.1\"");
+        json::push_member(&mut gelf, "host", &self.host);
+        match message.split_once('\n') {
+            Some((short_message, _)) => {
+                json::push_member(&mut gelf, "short_message", short_message);
+                json::push_member(&mut gelf, "full_message", &message);
+            },
+            None => json::push_member(&mut gelf, "short_message", &message),
+        }
+        let _ = write!(
+            gelf,
+            ",\"timestamp\":{}.{:06},\"level\":{}",
+            timestamp.as_secs(),
+            timestamp.subsec_micros(),
+            syslog_severity(log_object.severity),
+        );
+        json::push_member(&mut gelf, "_channel", channel_name);
+        json::push_member(&mut gelf, "_thread", log_object.thread.display(self.thread_format));
+        json::push_member(&mut gelf, "_file", log_object.location.file());
+        let _ = write!(gelf, ",\"_line\":{}", log_object.location.line());
+        for (key, value) in log_object.context.iter() {
+            json::push_member(&mut gelf, &field_name(key), value);
+        }
+        gelf.push('}');
+        match self.compression {
+            GelfCompression::None => self.send(gelf.as_bytes())?,
+            #[cfg(feature = "zlib")]
+            GelfCompression::Zlib => self.send(&miniz_oxide::deflate::compress_to_vec_zlib(gelf.as_bytes(), 6))?,
+            #[cfg(not(feature = "zlib"))]
+            GelfCompression::Zlib => return Err(Error::new(ErrorKind::Unsupported, "GELF zlib compression requires the `zlib` feature").into()),
+        }
+        Ok(())
+    }
+}
+
+impl<M: ChannelFilterMap> Sink for GelfSink<M> {
+    fn consume(&mut self, log_object: LogObject) {
+        if let Err(error) = self.write_record(log_object) {
+            self.error_policy.handle(&mut self.failed_writes, &mut self.reported, error, Some(log_object));
+        }
+    }
+
+    /// Failures are counted in [GelfSink::failed_writes] (unless [ErrorPolicy::Ignore]d) but otherwise returned instead of handled.
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        let result = self.write_record(log_object);
+        if result.is_err() {
+            self.error_policy.count(&mut self.failed_writes);
+        }
+        result
+    }
+}
+
+/// The additional fields that context keys mustn't override (`_id` is reserved by GELF).
+const RESERVED_FIELDS: [&str; 5] = ["_id", "_channel", "_thread", "_file", "_line"];
+
+/// Returns a valid additional field name for a context key.
+fn field_name(key: &str) -> String {
+    let mut name: String = std::iter::once('_')
+        .chain(key.chars().map(|c| if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '_' }))
+        .collect();
+    if RESERVED_FIELDS.contains(&name.as_str()) {
+        name.insert_str(0, "_context");
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, time::Duration};
+
+    use super::*;
+    use crate::{
+        filter_maps::StaticChannelFilterMap,
+        info, warning,
+        loggers::{Logger, ThreadInfo, single_threaded::SimpleLogger},
+    };
+
+    /// Receives a message, reassembling it if it's chunked.
+    fn receive(input: &UdpSocket) -> Vec<u8> {
+        let mut chunks = BTreeMap::new();
+        let mut buf = [0; 65536];
+        loop {
+            let len = input.recv(&mut buf).unwrap();
+            let datagram = &buf[..len];
+            if datagram[..2] != CHUNK_MAGIC {
+                assert!(chunks.is_empty());
+                return datagram.to_vec();
+            }
+            let (message_id, sequence, count) = (&datagram[2..10], datagram[10], datagram[11] as usize);
+            chunks.insert(sequence, (message_id.to_vec(), datagram[12..].to_vec()));
+            if chunks.len() == count {
+                let message_id = &chunks[&0].0;
+                assert!(chunks.values().all(|(id, _)| id == message_id));
+                return chunks.into_values().flat_map(|(_, chunk)| chunk).collect();
+            }
+        }
+    }
+
+    fn input() -> UdpSocket {
+        let input = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        input.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        input
+    }
+
+    #[test]
+    fn test_gelf() {
+        let input = input();
+        let mut sink = GelfSink::udp(input.local_addr().unwrap(), StaticChannelFilterMap(&["main", "net"])).unwrap();
+        sink.host = "host".into();
+        sink.thread_format = ThreadFormat::Id;
+        let logger = SimpleLogger::new(sink);
+        let line = line!() + 1;
+        info!(logger, "started");
+        {
+            let _id = crate::context::push("id", 1);
+            let _user = crate::context::push("user name", "alice");
+            let _line = crate::context::push("line", 0);
+            warning!(logger.channel(1), "first \"line\"\nsecond line");
+            warning!(logger.channel(2), "filtered");
+        }
+        let id = ThreadInfo::current().id;
+        let file = file!();
+        let first = String::from_utf8(receive(&input)).unwrap();
+        let (head, tail) = first.split_once(",\"timestamp\":").unwrap();
+        assert_eq!(head, "{\"version\":\"1.1\",\"host\":\"host\",\"short_message\":\"started\"");
+        let (timestamp, tail) = tail.split_once(',').unwrap();
+        assert_eq!(timestamp.split_once('.').unwrap().1.len(), 6);
+        assert_eq!(tail, format!("\"level\":6,\"_channel\":\"main\",\"_thread\":\"{id}\",\"_file\":\"{file}\",\"_line\":{line}}}"));
+        let second = String::from_utf8(receive(&input)).unwrap();
+        assert!(second.contains(",\"short_message\":\"first \\\"line\\\"\",\"full_message\":\"first \\\"line\\\"\\nsecond line\","));
+        assert!(second.contains("\"level\":4,\"_channel\":\"net\""));
+        assert!(second.ends_with(",\"_context_id\":\"1\",\"_user_name\":\"alice\",\"_context_line\":\"0\"}"));
+    }
+
+    #[test]
+    fn test_chunking() {
+        let input = input();
+        let mut sink = GelfSink::udp(input.local_addr().unwrap(), InvisibleChannelFilterMap).unwrap();
+        sink.chunk_size = 100;
+        let message = "0123456789".repeat(500);
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("{message}")));
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("{message}{message}{message}")));
+        assert_eq!(sink.failed_writes, 1);
+        assert!(sink.try_consume(LogObject::new(0, Level::INFO, format_args!("{message}{message}{message}"))).is_err());
+        assert_eq!(sink.failed_writes, 2);
+        let received = String::from_utf8(receive(&input)).unwrap();
+        assert!(received.contains(&format!("\"short_message\":\"{message}\"")));
+    }
+
+    #[cfg(not(feature = "zlib"))]
+    #[test]
+    fn test_zlib_unsupported() {
+        let input = input();
+        let mut sink = GelfSink::udp(input.local_addr().unwrap(), InvisibleChannelFilterMap).unwrap();
+        sink.compression = GelfCompression::Zlib;
+        let error = sink.try_consume(LogObject::new(0, Level::INFO, format_args!("compressed"))).unwrap_err();
+        assert!(matches!(error, SinkError::Io(e) if e.kind() == ErrorKind::Unsupported));
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_zlib() {
+        let input = input();
+        let mut sink = GelfSink::udp(input.local_addr().unwrap(), InvisibleChannelFilterMap).unwrap();
+        sink.compression = GelfCompression::Zlib;
+        sink.chunk_size = 64;
+        let message = (0..2000).map(|i| (i * 7919 % 251) as u8 as char).collect::<String>();
+        sink.consume(LogObject::new(0, Level::ERROR, format_args!("{message}")));
+        let received = miniz_oxide::inflate::decompress_to_vec_zlib(&receive(&input)).unwrap();
+        assert!(String::from_utf8(received).unwrap().contains("\"level\":3"));
+    }
+}
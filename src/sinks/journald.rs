@@ -0,0 +1,330 @@
+//! [JournaldSink] for sending [LogObject]s to systemd-journald.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Write},
+    mem::size_of,
+    os::{fd::{AsRawFd, FromRawFd, RawFd}, unix::net::UnixDatagram},
+    path::Path,
+};
+
+use crate::{
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{Level, LogObject, ThreadFormat},
+    sinks::{ErrorPolicy, Sink, SinkError, process, syslog_severity},
+};
+
+/// The path of journald's native protocol socket.
+pub const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// A [Sink] that sends [LogObject]s via a [ChannelFilterMap] to systemd-journald using its
+/// [native protocol](https://systemd.io/JOURNAL_NATIVE_PROTOCOL/).
+///
+/// Besides `MESSAGE` and `SYSLOG_IDENTIFIER`, every entry carries the fields
+/// `PRIORITY`, `CODE_FILE`, `CODE_LINE`, `LOGIDIZE_CHANNEL`, `THREAD` and (if available) `TID`.
+/// Every key of the [LogObject::context] becomes a field named like the key in uppercase with invalid characters
+/// replaced by `_` (and prefixed with `CONTEXT_` unless it starts with a letter or if it would clash with
+/// one of the fields above or another field journald interprets, like `MESSAGE_ID` or `SYSLOG_PID`).
+/// Entries too large for a single datagram are passed to journald in a sealed memfd.
+#[derive(Debug)]
+pub struct JournaldSink<M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// The [ChannelFilterMap] used for `LOGIDIZE_CHANNEL`.
+    pub channel_map: M,
+    /// How failures of [JournaldSink::socket] are handled by [Sink::consume()].
+    pub error_policy: ErrorPolicy,
+    /// The number of failed sends that weren't ignored according to [JournaldSink::error_policy].
+    pub failed_writes: u64,
+    /// The sink's minimum severity level. [JournaldSink] won't log [LogObject]s of lower severity.
+    pub min_severity: Level,
+    /// Whether the sink is muted. A muted [JournaldSink] won't log anything.
+    pub muted: bool,
+    /// The socket connected to journald.
+    pub socket: UnixDatagram,
+    /// The `SYSLOG_IDENTIFIER`.
+    pub syslog_identifier: String,
+    /// How threads are displayed in `THREAD`.
+    pub thread_format: ThreadFormat,
+    reported: bool,
+}
+
+impl<M: ChannelFilterMap> JournaldSink<M> {
+    /// Constructs a new [JournaldSink] with default settings (that shouldn't be relied upon).
+    ///
+    /// The socket has to be connected to journald (see [JournaldSink::connect()]).
+    #[must_use]
+    pub fn new(socket: UnixDatagram, channel_map: M) -> Self {
+        Self {
+            channel_map,
+            error_policy: ErrorPolicy::Count,
+            failed_writes: 0,
+            min_severity: Level::DEBUG,
+            muted: false,
+            socket,
+            syslog_identifier: process::process_name().unwrap_or_default(),
+            thread_format: ThreadFormat::NameId,
+            reported: false,
+        }
+    }
+
+    /// Constructs a new [JournaldSink] connected to the socket at `path` (usually [JOURNALD_SOCKET]).
+    pub fn connect(path: impl AsRef<Path>, channel_map: M) -> std::io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self::new(socket, channel_map))
+    }
+
+    fn send(&self, entry: &[u8]) -> std::io::Result<()> {
+        match self.socket.send(entry) {
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => {
+                let memfd = sealed_memfd(entry)?;
+                send_fd(&self.socket, memfd.as_raw_fd())
+            },
+            result => result.map(drop),
+        }
+    }
+
+    /// Encodes and sends `log_object` unless it's filtered out.
+    fn write_record(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        if self.muted || log_object.severity < self.min_severity {
+            return Ok(());
+        }
+        let Some(channel_name) = self.channel_map.filter_map(&log_object) else {
+            return Ok(());
+        };
+        let mut entry = Vec::new();
+        push_field(&mut entry, "MESSAGE", &log_object.message.to_string());
+        push_field(&mut entry, "PRIORITY", &syslog_severity(log_object.severity).to_string());
+        if !self.syslog_identifier.is_empty() {
+            push_field(&mut entry, "SYSLOG_IDENTIFIER", &self.syslog_identifier);
+        }
+        push_field(&mut entry, "CODE_FILE", log_object.location.file());
+        push_field(&mut entry, "CODE_LINE", &log_object.location.line().to_string());
+        push_field(&mut entry, "LOGIDIZE_CHANNEL", &channel_name.to_string());
+        push_field(&mut entry, "THREAD", &log_object.thread.display(self.thread_format).to_string());
+        if let Some(os_id) = log_object.thread.os_id {
+            push_field(&mut entry, "TID", &os_id.to_string());
+        }
+        for (key, value) in log_object.context.iter() {
+            push_field(&mut entry, &field_name(key), value);
+        }
+        Ok(self.send(&entry)?)
+    }
+}
+
+impl<M: ChannelFilterMap> Sink for JournaldSink<M> {
+    fn consume(&mut self, log_object: LogObject) {
+        if let Err(error) = self.write_record(log_object) {
+            self.error_policy.handle(&mut self.failed_writes, &mut self.reported, error, Some(log_object));
+        }
+    }
+
+    /// Failures are counted in [JournaldSink::failed_writes] (unless [ErrorPolicy::Ignore]d) but otherwise returned instead of handled.
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        let result = self.write_record(log_object);
+        if result.is_err() {
+            self.error_policy.count(&mut self.failed_writes);
+        }
+        result
+    }
+}
+
+/// The fields written for every entry or otherwise interpreted by journald, which context keys mustn't override.
+const RESERVED_FIELDS: [&str; 16] = [
+    "MESSAGE", "MESSAGE_ID", "PRIORITY", "CODE_FILE", "CODE_LINE", "CODE_FUNC", "ERRNO", "INVOCATION_ID",
+    "SYSLOG_FACILITY", "SYSLOG_IDENTIFIER", "SYSLOG_PID", "SYSLOG_TIMESTAMP", "SYSLOG_RAW", "LOGIDIZE_CHANNEL", "THREAD", "TID",
+];
+
+/// Returns a valid journald field name for a context key.
+fn field_name(key: &str) -> String {
+    let mut name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) || RESERVED_FIELDS.contains(&name.as_str()) {
+        name.insert_str(0, "CONTEXT_");
+    }
+    name.truncate(64);
+    name
+}
+
+/// Appends a field to an entry, using the binary encoding if the value contains a newline.
+fn push_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/// Returns a memfd containing `data` that is sealed against any modification.
+fn sealed_memfd(data: &[u8]) -> std::io::Result<File> {
+    // SAFETY: the name is a valid C string and the returned fd is owned by the File
+    let fd = unsafe { libc::memfd_create(c"logidize-journald".as_ptr(), libc::MFD_ALLOW_SEALING | libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid file descriptor
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(data)?;
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    // SAFETY: fd is valid for the lifetime of file
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(file)
+}
+
+/// Sends an empty datagram carrying `fd` as `SCM_RIGHTS` over a connected socket.
+fn send_fd(socket: &UnixDatagram, fd: RawFd) -> std::io::Result<()> {
+    let mut control = [0u64; 4];
+    // SAFETY: msghdr is a plain C struct for which all zeroes is a valid (empty) value
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_control = control.as_mut_ptr().cast();
+    // SAFETY: CMSG_SPACE only computes a size
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(size_of::<RawFd>() as u32) } as _;
+    if msg.msg_controllen as usize > size_of::<[u64; 4]>() {
+        return Err(Error::from(ErrorKind::Unsupported));
+    }
+    // SAFETY: msg_control points to a suitably aligned buffer large enough for one header with an fd
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), fd);
+    }
+    // SAFETY: msg and the buffers it points to are valid for the duration of the call
+    match unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) } {
+        -1 => Err(Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, io::{Read, Seek}};
+
+    use super::*;
+    use crate::{
+        filter_maps::StaticChannelFilterMap,
+        info, warning,
+        loggers::{Logger, single_threaded::SimpleLogger},
+    };
+
+    struct Journal {
+        path: std::path::PathBuf,
+        socket: UnixDatagram,
+    }
+
+    impl Journal {
+        fn bind(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("logidize-journald-{name}-{}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let socket = UnixDatagram::bind(&path).unwrap();
+            Self { path, socket }
+        }
+
+        /// Receives an entry, reading it from a passed memfd if the datagram is empty.
+        fn receive(&self) -> Vec<u8> {
+            let mut buf = vec![0; 1 << 16];
+            let mut control = [0u64; 4];
+            let mut iov = libc::iovec { iov_base: buf.as_mut_ptr().cast(), iov_len: buf.len() };
+            let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr().cast();
+            msg.msg_controllen = size_of::<[u64; 4]>() as _;
+            let len = unsafe { libc::recvmsg(self.socket.as_raw_fd(), &mut msg, 0) };
+            assert!(len >= 0, "{}", Error::last_os_error());
+            buf.truncate(len as usize);
+            if len > 0 {
+                return buf;
+            }
+            let cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+            assert!(!cmsg.is_null());
+            assert_eq!(unsafe { (*cmsg).cmsg_type }, libc::SCM_RIGHTS);
+            let fd = unsafe { std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>()) };
+            let mut file = unsafe { File::from_raw_fd(fd) };
+            let seals = unsafe { libc::fcntl(fd, libc::F_GET_SEALS) };
+            assert_ne!(seals & libc::F_SEAL_WRITE, 0);
+            file.rewind().unwrap();
+            file.read_to_end(&mut buf).unwrap();
+            buf
+        }
+    }
+
+    impl Drop for Journal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn parse(mut entry: &[u8]) -> BTreeMap<String, String> {
+        let mut fields = BTreeMap::new();
+        while let Some(end) = entry.iter().position(|&b| b == b'\n' || b == b'=') {
+            let name = String::from_utf8(entry[..end].to_vec()).unwrap();
+            let value;
+            if entry[end] == b'=' {
+                let len = entry[end..].iter().position(|&b| b == b'\n').unwrap() - 1;
+                value = &entry[end + 1..end + 1 + len];
+                entry = &entry[end + len + 2..];
+            } else {
+                let len = u64::from_le_bytes(entry[end + 1..end + 9].try_into().unwrap()) as usize;
+                value = &entry[end + 9..end + 9 + len];
+                assert_eq!(entry[end + 9 + len], b'\n');
+                entry = &entry[end + len + 10..];
+            }
+            fields.insert(name, String::from_utf8(value.to_vec()).unwrap());
+        }
+        assert!(entry.is_empty());
+        fields
+    }
+
+    #[test]
+    fn test_fields() {
+        let journal = Journal::bind("fields");
+        let mut sink = JournaldSink::connect(&journal.path, StaticChannelFilterMap(&["main", "net"])).unwrap();
+        sink.syslog_identifier = "app".into();
+        sink.min_severity = Level::INFO;
+        let logger = SimpleLogger::new(sink);
+        crate::debug!(logger, "filtered");
+        let line = line!() + 1;
+        info!(logger, "started");
+        let guard = crate::context::push("request-id", 42);
+        let _user = crate::context::push("1user", "multi\nline");
+        let _message = crate::context::push("message", "spoofed");
+        warning!(logger.channel(1), "multi\nline");
+        drop(guard);
+        warning!(logger.channel(2), "filtered");
+        let first = parse(&journal.receive());
+        assert_eq!(first["MESSAGE"], "started");
+        assert_eq!(first["PRIORITY"], "6");
+        assert_eq!(first["SYSLOG_IDENTIFIER"], "app");
+        assert_eq!(first["CODE_FILE"], file!());
+        assert_eq!(first["CODE_LINE"], line.to_string());
+        assert_eq!(first["LOGIDIZE_CHANNEL"], "main");
+        assert!(first["THREAD"].ends_with(&format!("#{}", crate::loggers::ThreadInfo::current().id)));
+        assert!(!first.contains_key("REQUEST_ID"));
+        let second = parse(&journal.receive());
+        assert_eq!(second["MESSAGE"], "multi\nline");
+        assert_eq!(second["PRIORITY"], "4");
+        assert_eq!(second["LOGIDIZE_CHANNEL"], "net");
+        assert_eq!(second["REQUEST_ID"], "42");
+        assert_eq!(second["CONTEXT_1USER"], "multi\nline");
+        assert_eq!(second["CONTEXT_MESSAGE"], "spoofed");
+        assert_eq!(logger.sink().failed_writes, 0);
+    }
+
+    #[test]
+    fn test_memfd() {
+        let journal = Journal::bind("memfd");
+        let mut sink = JournaldSink::connect(&journal.path, InvisibleChannelFilterMap).unwrap();
+        let message = "x".repeat(4 << 20);
+        sink.consume(LogObject::new(0, Level::ERROR, format_args!("{message}")));
+        assert_eq!(sink.failed_writes, 0);
+        let entry = parse(&journal.receive());
+        assert_eq!(entry["MESSAGE"], message);
+        assert_eq!(entry["PRIORITY"], "3");
+    }
+}
@@ -0,0 +1,82 @@
+//! JSON encoding of [LogObject]s.
+
+use std::fmt::{Display, Write as _};
+
+use crate::{
+    loggers::{LogObject, ThreadFormat},
+    sinks::{logfmt::{context_key, level_name}, timestamp::Rfc3339},
+    writers::Write,
+};
+
+/// Writes a [LogObject] as a single-line JSON object with a single call to [Write::write_all()].
+pub(crate) fn write_record(
+    output: &mut impl Write,
+    log_object: &LogObject,
+    channel: impl Display,
+    thread_format: Option<ThreadFormat>,
+) -> std::io::Result<()> {
+    let mut line = format!("{{\"ts\":\"{}\",\"level\":\"{}\"", Rfc3339(log_object.time), level_name(log_object.severity));
+    push_member(&mut line, "channel", channel);
+    if let Some(format) = thread_format {
+        push_member(&mut line, "thread", log_object.thread.display(format));
+    }
+    for (key, value) in log_object.context.iter() {
+        push_member(&mut line, &context_key(key), value);
+    }
+    push_member(&mut line, "msg", log_object.message);
+    line.push_str("}\n");
+    output.write_all(line.as_bytes())
+}
+
+/// Appends `,"key":"value"` to `json`, escaping the value.
+pub(crate) fn push_member(json: &mut String, key: &str, value: impl Display) {
+    json.push(',');
+    push_string(json, key);
+    json.push(':');
+    push_string(json, value);
+}
+
+/// Appends `value` to `json` as a JSON string.
+pub(crate) fn push_string(json: &mut String, value: impl Display) {
+    json.push('"');
+    let start = json.len();
+    let _ = write!(json, "{value}");
+    if json[start..].chars().any(|c| c == '"' || c == '\\' || c.is_control()) {
+        let value = json.split_off(start);
+        for c in value.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c.is_control() => {
+                    let _ = write!(json, "\\u{:04x}", c as u32);
+                },
+                c => json.push(c),
+            }
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> String {
+        let mut json = String::new();
+        push_string(&mut json, value);
+        json
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(string("plain text"), "\"plain text\"");
+        assert_eq!(string(""), "\"\"");
+        assert_eq!(string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(string("C:\\path"), "\"C:\\\\path\"");
+        assert_eq!(string("line\nbreak\t\x1b[0m"), "\"line\\nbreak\\t\\u001b[0m\"");
+        assert_eq!(string("ünïcödé"), "\"ünïcödé\"");
+    }
+}
@@ -0,0 +1,125 @@
+//! [logfmt](https://brandur.org/logfmt) encoding of [LogObject]s.
+
+use std::{
+    borrow::Cow,
+    fmt::{Display, Write as _},
+};
+
+use crate::{
+    loggers::{Level, LogObject, ThreadFormat},
+    sinks::timestamp::Rfc3339,
+    writers::Write,
+};
+
+/// Returns the lowercase name of the level as commonly used by logfmt consumers.
+pub(crate) const fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::DEBUG    => "debug",
+        Level::INFO     => "info",
+        Level::WARNING  => "warning",
+        Level::ERROR    => "error",
+        Level::CRITICAL => "critical",
+    }
+}
+
+/// The keys of the pairs (or JSON members) written for every [LogObject].
+const RESERVED_KEYS: [&str; 5] = ["ts", "level", "channel", "thread", "msg"];
+
+/// Returns the key a context key is written as, prefixed with `context_` if it clashes with [RESERVED_KEYS].
+pub(crate) fn context_key(key: &str) -> Cow<'_, str> {
+    match RESERVED_KEYS.contains(&key) {
+        true => format!("context_{key}").into(),
+        false => key.into(),
+    }
+}
+
+/// Returns a valid logfmt key for a context key (see [context_key()]) with spaces, `=`, `"` and control characters replaced by `_`.
+fn pair_key(key: &str) -> Cow<'_, str> {
+    let invalid = |c: char| c <= ' ' || c == '=' || c == '"' || c.is_control();
+    match context_key(key) {
+        key if key.is_empty() => "_".into(),
+        key if key.contains(invalid) => key.chars().map(|c| if invalid(c) { '_' } else { c }).collect::<String>().into(),
+        key => key,
+    }
+}
+
+/// Writes a [LogObject] as a single logfmt line with a single call to [Write::write_all()].
+pub(crate) fn write_record(
+    output: &mut impl Write,
+    log_object: &LogObject,
+    channel: impl Display,
+    thread_format: Option<ThreadFormat>,
+) -> std::io::Result<()> {
+    let mut line = format!("ts={} level={}", Rfc3339(log_object.time), level_name(log_object.severity));
+    push_pair(&mut line, "channel", channel);
+    if let Some(format) = thread_format {
+        push_pair(&mut line, "thread", log_object.thread.display(format));
+    }
+    for (key, value) in log_object.context.iter() {
+        push_pair(&mut line, &pair_key(key), value);
+    }
+    push_pair(&mut line, "msg", log_object.message);
+    line.push('\n');
+    output.write_all(line.as_bytes())
+}
+
+/// Appends ` key=value` to `line`, quoting and escaping the value if necessary.
+pub(crate) fn push_pair(line: &mut String, key: &str, value: impl Display) {
+    let _ = write!(line, " {key}=");
+    let start = line.len();
+    let _ = write!(line, "{value}");
+    let needs_quotes = start == line.len() || line[start..].chars().any(|c| c <= ' ' || c == '=' || c == '"' || c.is_control());
+    if !needs_quotes {
+        return;
+    }
+    let value = line.split_off(start);
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(line, "\\u{:04x}", c as u32);
+            },
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(value: &str) -> String {
+        let mut line = String::new();
+        push_pair(&mut line, "k", value);
+        line
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(pair("plain"), " k=plain");
+        assert_eq!(pair(""), " k=\"\"");
+        assert_eq!(pair("a b"), " k=\"a b\"");
+        assert_eq!(pair("a=b"), " k=\"a=b\"");
+        assert_eq!(pair("say \"hi\""), " k=\"say \\\"hi\\\"\"");
+        assert_eq!(pair("C:\\path"), " k=C:\\path");
+        assert_eq!(pair("C:\\my path"), " k=\"C:\\\\my path\"");
+        assert_eq!(pair("line\nbreak\x1b[0m"), " k=\"line\\nbreak\\u001b[0m\"");
+        assert_eq!(pair("ünïcödé"), " k=ünïcödé");
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(pair_key("request_id"), "request_id");
+        assert_eq!(pair_key("user name"), "user_name");
+        assert_eq!(pair_key("a=b\"c\n"), "a_b_c_");
+        assert_eq!(pair_key(""), "_");
+        assert_eq!(pair_key("msg"), "context_msg");
+        assert_eq!(pair_key("level"), "context_level");
+    }
+}
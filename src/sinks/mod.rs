@@ -0,0 +1,841 @@
+//! Sensible [Sink]s.
+
+mod capture;
+mod dedup;
+mod failover;
+mod gelf;
+#[cfg(target_os = "linux")]
+mod journald;
+mod json;
+mod logfmt;
+mod multiline;
+mod network;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod process;
+mod router;
+mod sampling;
+mod syslog;
+mod throttle;
+mod timestamp;
+
+use std::{
+    error::Error,
+    fmt::{Arguments, Display},
+    sync::{Arc, Mutex, PoisonError},
+    time::UNIX_EPOCH,
+};
+
+use multiline::{Escaped, Record};
+
+use crate::{
+    colors::{ColorMode, Styled, Theme},
+    context::Context,
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{DisplayThread, Level, LogObject, ThreadFormat},
+    writers::{AtomicStderrWriter, LogWrite, Write},
+};
+
+pub use capture::CaptureSink;
+pub use dedup::DedupSink;
+pub use failover::FailoverSink;
+pub use gelf::{GelfCompression, GelfSink};
+#[cfg(target_os = "linux")]
+pub use journald::{JOURNALD_SOCKET, JournaldSink};
+pub use multiline::MultilinePolicy;
+pub use network::{Framing, NetworkAddress, NetworkSink};
+#[cfg(feature = "otlp")]
+pub use otlp::{OtlpSink, TraceContext, otlp_severity};
+pub use router::{ChannelSelector, Route, RouteMode, RouterSink};
+pub use sampling::{Sampling, SamplingRng, SamplingSink};
+pub(crate) use sampling::{SamplingCounts, splitmix64};
+pub use syslog::{Facility, SyslogFormat, SyslogSink, SyslogTransport, syslog_severity};
+pub use throttle::{ThrottleKey, ThrottleLimit, ThrottleSink};
+
+/// An error reported by [Sink::try_consume()].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SinkError {
+    /// The underlying output failed.
+    Io(std::io::Error),
+}
+
+impl Display for SinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SinkError::Io(e) => write!(f, "failed to write log: {e}"),
+        }
+    }
+}
+
+impl Error for SinkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SinkError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for SinkError {
+    fn from(e: std::io::Error) -> Self {
+        SinkError::Io(e)
+    }
+}
+
+/// A trait for objects which are [LogObject] sinks.
+pub trait Sink {
+    /// Consumes a [LogObject] (i.e. logs it).
+    fn consume(&mut self, log_object: LogObject);
+
+    /// Consumes a [LogObject] (i.e. logs it), reporting failure instead of handling it.
+    ///
+    /// The default implementation calls [Sink::consume()] and always succeeds.
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.consume(log_object);
+        Ok(())
+    }
+
+    /// Flushes any output buffered by the sink.
+    ///
+    /// The default implementation does nothing.
+    fn flush(&mut self) {}
+}
+
+impl<T: FnMut(LogObject)> Sink for T {
+    fn consume(&mut self, log_object: LogObject) {
+        self(log_object);
+    }
+}
+
+/// Constructs a [LogObject] synthesized by a sink (like a summary) with the time and thread of the [LogObject] triggering it.
+///
+/// Without a trigger (e.g. when flushing), the current time and thread are used.
+#[track_caller]
+pub(crate) fn synthesize<'a>(trigger: Option<&LogObject>, channel_id: usize, severity: Level, message: Arguments<'a>) -> LogObject<'a> {
+    let log_object = LogObject::new(channel_id, severity, message);
+    match trigger {
+        Some(trigger) => LogObject { thread: trigger.thread, thread_id: trigger.thread_id, time: trigger.time, ..log_object },
+        None => log_object,
+    }
+}
+
+/// Passes `log_object` to `sink` with [Sink::try_consume()] if `fallible` or with [Sink::consume()] (always succeeding) otherwise.
+///
+/// Wrapping sinks use it so that the wrapped sink handles failures according to its own policy unless they're reported.
+pub(crate) fn forward<S: Sink + ?Sized>(sink: &mut S, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+    match fallible {
+        true => sink.try_consume(log_object),
+        false => {
+            sink.consume(log_object);
+            Ok(())
+        },
+    }
+}
+
+/// What a sink like [WriteSink] does when its output fails.
+#[derive(Clone, Default)]
+pub enum ErrorPolicy {
+    /// Failures are ignored entirely.
+    Ignore,
+    /// Failures are counted (e.g. in [WriteSink::failed_writes]).
+    #[default]
+    Count,
+    /// Failures are counted and the first one is reported to [Stderr](std::io::Stderr).
+    ReportOnce,
+    /// Failures are counted and the [LogObject] that couldn't be written is passed to the [Sink] instead
+    /// (usually created with [ErrorPolicy::fallback()]).
+    Fallback(Arc<Mutex<dyn Sink + Send>>),
+}
+
+/// The output format of a [WriteSink].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Format {
+    /// Human-readable text like `[1700000000][INFO][net]: message` or `[1700000000][INFO][net][request_id=42]: message`
+    /// if the [LogObject::context] isn't empty.
+    ///
+    /// Control characters in the context are escaped unless [WriteSink::multiline] is [MultilinePolicy::Raw].
+    ///
+    /// Colored according to [WriteSink::color_mode] and [WriteSink::theme].
+    #[default]
+    Text,
+    /// [logfmt](https://brandur.org/logfmt) like `ts=2023-11-14T22:13:20.000000Z level=info channel=net msg="a message"`.
+    ///
+    /// The [LogObject::context] is included as additional pairs before `msg`. Keys clashing with the other pairs are prefixed
+    /// with `context_` (e.g. `context_msg`) and spaces, `=`, `"` and control characters in keys are replaced by `_`.
+    ///
+    /// Never colored and always a single line per [LogObject].
+    Logfmt,
+    /// A JSON object per line like `{"ts":"2023-11-14T22:13:20.000000Z","level":"info","channel":"net","msg":"a message"}`.
+    ///
+    /// The [LogObject::context] is included as additional string members before `msg`.
+    /// Keys clashing with the other members are prefixed with `context_` (e.g. `context_msg`).
+    ///
+    /// Never colored and always a single line per [LogObject].
+    Json,
+}
+
+impl ErrorPolicy {
+    /// Constructs a new [ErrorPolicy::Fallback] to `sink`.
+    ///
+    /// ```
+    /// # use logidize::{*, loggers::{LogObject, single_threaded::SimpleLogger}, sinks::{CaptureSink, ErrorPolicy, WriteSink}};
+    /// let capture = CaptureSink::new();
+    /// let logger = SimpleLogger::new(WriteSink::<Vec<u8>>::default());
+    /// logger.sink().error_policy = ErrorPolicy::fallback(capture.clone());
+    /// logger.sink().error_policy = ErrorPolicy::fallback(|log_object: LogObject| eprintln!("{}", log_object.message));
+    /// ```
+    pub fn fallback(sink: impl Sink + Send + 'static) -> Self {
+        ErrorPolicy::Fallback(Arc::new(Mutex::new(sink)))
+    }
+
+    /// Handles a failure to log `log_object` (or [None] for other failures like flushing) according to the policy.
+    ///
+    /// `failures` is the sink's counter of failures that weren't ignored
+    /// and `reported` whether a failure was reported according to [ErrorPolicy::ReportOnce] yet.
+    pub fn handle(&self, failures: &mut u64, reported: &mut bool, error: SinkError, log_object: Option<LogObject>) {
+        if let ErrorPolicy::Ignore = self {
+            return;
+        }
+        *failures += 1;
+        match self {
+            ErrorPolicy::ReportOnce if !*reported => {
+                *reported = true;
+                let _ = writeln!(std::io::stderr(), "logidize: {error}");
+            },
+            ErrorPolicy::Fallback(_) => {
+                if let Some(log_object) = log_object {
+                    self.fall_back(log_object);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Counts a failure returned by [Sink::try_consume()] (unless it's [ErrorPolicy::Ignore]d) without handling it otherwise.
+    pub(crate) fn count(&self, failures: &mut u64) {
+        if !matches!(self, ErrorPolicy::Ignore) {
+            *failures += 1;
+        }
+    }
+
+    /// Passes `log_object` to the [ErrorPolicy::Fallback] sink (if that's the policy) without counting a failure.
+    pub(crate) fn fall_back(&self, log_object: LogObject) {
+        if let ErrorPolicy::Fallback(fallback) = self {
+            fallback.lock().unwrap_or_else(PoisonError::into_inner).consume(log_object);
+        }
+    }
+}
+
+impl std::fmt::Debug for ErrorPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorPolicy::Ignore => f.write_str("Ignore"),
+            ErrorPolicy::Count => f.write_str("Count"),
+            ErrorPolicy::ReportOnce => f.write_str("ReportOnce"),
+            ErrorPolicy::Fallback(_) => f.write_str("Fallback(..)"),
+        }
+    }
+}
+
+/// A [Sink] that outputs formatted [LogObject]s via a [ChannelFilterMap] to a [Write].
+///
+/// Writes to [AtomicStderrWriter] by default.
+#[derive(Clone, Debug)]
+pub struct WriteSink<W: Write = AtomicStderrWriter, M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// The [ChannelFilterMap] used.
+    pub channel_map: M,
+    /// Whether the output is colored using [WriteSink::theme].
+    ///
+    /// [ColorMode::Auto] is resolved once (see [WriteSink::colors()]).
+    pub color_mode: ColorMode,
+    /// Called on [WriteSink::output] after each written [LogObject].
+    ///
+    /// [WriteSink::new()] sets it to [LogWrite::end_record()], [WriteSink::from_write()] to a function doing nothing.
+    pub end_record: fn(&mut W, Level) -> std::io::Result<()>,
+    /// How failures of the underlying [Write] are handled by [Sink::consume()] and [Sink::flush()].
+    ///
+    /// [Sink::try_consume()] only counts failures and returns them to the caller.
+    pub error_policy: ErrorPolicy,
+    /// The number of failed writes (and flushes) that weren't ignored according to [WriteSink::error_policy]
+    /// (including those returned by [Sink::try_consume()]).
+    pub failed_writes: u64,
+    /// Returns whether [WriteSink::output] is a terminal (used to resolve [ColorMode::Auto]).
+    ///
+    /// [WriteSink::new()] sets it to [LogWrite::is_terminal()], [WriteSink::from_write()] to a function returning `false`.
+    pub is_terminal: fn(&W) -> bool,
+    /// Whether [Level::CRITICAL] [LogObject]s should flush the [Write] immediately.
+    pub flush_on_critical: bool,
+    /// The output [Format].
+    pub format: Format,
+    /// The sink's minimum severity level. [WriteSink] won't log [LogObject]s of lower severity.
+    pub min_severity: Level,
+    /// How messages spanning multiple lines are written.
+    pub multiline: MultilinePolicy,
+    /// Whether the sink is muted. A muted [WriteSink] won't log anything.
+    pub muted: bool,
+    /// The underlying [Write].
+    pub output: W,
+    /// The [Theme] used to color the output. [Theme::PLAIN] disables colors.
+    pub theme: Theme,
+    /// How the logging thread is included in the logs or [None] if it isn't.
+    pub thread_format: Option<ThreadFormat>,
+    colors: Option<(ColorMode, bool)>,
+    reported: bool,
+}
+
+impl<W: Write, M: ChannelFilterMap> WriteSink<W, M> {
+    /// Constructs a new [WriteSink] to a [Write] that doesn't implement [LogWrite] with default settings (that shouldn't be relied upon).
+    ///
+    /// [WriteSink::output] is never considered a terminal and isn't told where records end,
+    /// so [Write]rs relying on [LogWrite::end_record()] (like [BufferedWriter](crate::writers::BufferedWriter)) need [WriteSink::new()].
+    #[must_use]
+    pub const fn from_write(output: W, channel_map: M) -> Self {
+        Self {
+            channel_map,
+            color_mode: ColorMode::Auto,
+            end_record: |_, _| Ok(()),
+            error_policy: ErrorPolicy::Count,
+            failed_writes: 0,
+            flush_on_critical: false,
+            format: Format::Text,
+            is_terminal: |_| false,
+            min_severity: Level::DEBUG,
+            multiline: MultilinePolicy::Raw,
+            muted: false,
+            output,
+            theme: Theme::CLASSIC,
+            thread_format: None,
+            colors: None,
+            reported: false,
+        }
+    }
+
+    /// Returns whether the output is colored according to [WriteSink::color_mode].
+    ///
+    /// The result is cached until [WriteSink::color_mode] changes or [WriteSink::redetect_colors()] is called.
+    pub fn colors(&mut self) -> bool {
+        match self.colors {
+            Some((mode, colors)) if mode == self.color_mode => colors,
+            _ => {
+                let colors = self.color_mode.enabled((self.is_terminal)(&self.output));
+                self.colors = Some((self.color_mode, colors));
+                colors
+            },
+        }
+    }
+
+    /// Clears the cached result of [WriteSink::colors()] (e.g. after replacing [WriteSink::output]).
+    pub fn redetect_colors(&mut self) {
+        self.colors = None;
+    }
+
+    fn write_text(&mut self, log_object: &LogObject, channel_name: impl Display) -> std::io::Result<()> {
+        let secs_since_epoch = match log_object.time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+        let theme = match self.colors() {
+            true => &self.theme,
+            false => &Theme::PLAIN,
+        };
+        let header = Header {
+            thread: self.thread_format.map(|format| Styled(theme.thread, log_object.thread.display(format))),
+            time: Styled(theme.time, secs_since_epoch),
+            level: Styled(theme.level(log_object.severity), log_object.severity),
+            channel: Styled(theme.channel(log_object.channel_id), channel_name),
+            context: log_object.context,
+            escape_context: self.multiline != MultilinePolicy::Raw,
+        };
+        writeln!(self.output, "{}", Record { header, message: log_object.message, policy: self.multiline })
+    }
+
+    /// Writes `log_object` (unless it's filtered) and returns whether [WriteSink::output] should be flushed now.
+    fn write(&mut self, log_object: &LogObject) -> std::io::Result<bool> {
+        if self.muted || log_object.severity < self.min_severity {
+            return Ok(false);
+        }
+        let Some(channel_name) = self.channel_map.filter_map(log_object) else {
+            return Ok(false);
+        };
+        match self.format {
+            Format::Text => self.write_text(log_object, channel_name)?,
+            Format::Logfmt => logfmt::write_record(&mut self.output, log_object, channel_name, self.thread_format)?,
+            Format::Json => json::write_record(&mut self.output, log_object, channel_name, self.thread_format)?,
+        }
+        (self.end_record)(&mut self.output, log_object.severity)?;
+        Ok(self.flush_on_critical && log_object.severity == Level::CRITICAL)
+    }
+}
+
+impl<W: LogWrite, M: ChannelFilterMap> WriteSink<W, M> {
+    /// Constructs a new [WriteSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(output: W, channel_map: M) -> Self {
+        let mut sink = Self::from_write(output, channel_map);
+        sink.end_record = W::end_record;
+        sink.is_terminal = W::is_terminal;
+        sink
+    }
+}
+
+impl<W: LogWrite + Default, M: ChannelFilterMap + Default> Default for WriteSink<W, M> {
+    fn default() -> Self {
+        Self::new(Default::default(), Default::default())
+    }
+}
+
+impl<W: Write, M: ChannelFilterMap> Sink for WriteSink<W, M> {
+    /// Only failed writes pass the [LogObject] to [ErrorPolicy::Fallback], a failed flush (on [Level::CRITICAL]) doesn't.
+    fn consume(&mut self, log_object: LogObject) {
+        match self.write(&log_object) {
+            Ok(true) => self.flush(),
+            Ok(false) => (),
+            Err(error) => self.error_policy.handle(&mut self.failed_writes, &mut self.reported, error.into(), Some(log_object)),
+        }
+    }
+
+    /// Failures are counted in [WriteSink::failed_writes] (unless [ErrorPolicy::Ignore]d) but otherwise returned instead of handled.
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        let result = self.write(&log_object).and_then(|flush| if flush { self.output.flush() } else { Ok(()) });
+        if result.is_err() {
+            self.error_policy.count(&mut self.failed_writes);
+        }
+        Ok(result?)
+    }
+
+    fn flush(&mut self) {
+        if let Err(error) = self.output.flush() {
+            self.error_policy.handle(&mut self.failed_writes, &mut self.reported, error.into(), None);
+        }
+    }
+}
+
+/// The part of [WriteSink]'s output preceding the message.
+struct Header<'a, C: Display> {
+    thread: Option<Styled<DisplayThread>>,
+    time: Styled<i64>,
+    level: Styled<Level>,
+    channel: Styled<C>,
+    context: Context<'a>,
+    /// Whether control characters in the context are escaped (like in the message unless it's [MultilinePolicy::Raw]).
+    escape_context: bool,
+}
+
+impl<C: Display> Display for Header<'_, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(thread) = &self.thread {
+            write!(f, "[{thread}]")?;
+        }
+        write!(f, "[{}][{}][{}]", self.time, self.level, self.channel)?;
+        if self.context.is_empty() {
+            return f.write_str(": ");
+        }
+        if !self.escape_context {
+            return write!(f, "[{}]: ", self.context);
+        }
+        f.write_str("[")?;
+        for (i, (key, value)) in self.context.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}={}", Escaped(key), Escaped(value))?;
+        }
+        f.write_str("]: ")
+    }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Default, Hash)]
+pub struct MultiSink<T1: Sink, T2: Sink>(pub T1, pub T2);
+
+impl<T1: Sink, T2: Sink> Sink for MultiSink<T1, T2> {
+    fn consume(&mut self, log_object: LogObject) {
+        self.0.consume(log_object);
+        self.1.consume(log_object);
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        let result = self.0.try_consume(log_object);
+        self.1.try_consume(log_object).and(result)
+    }
+
+    fn flush(&mut self) {
+        self.0.flush();
+        self.1.flush();
+    }
+}
+
+/// Creates a `MultiSink` with the given given sink expressions.
+#[macro_export]
+macro_rules! multi_sink {
+    ($head:expr, $tail:expr $(,)?) => {
+        $crate::sinks::MultiSink($head, $tail)
+    };
+
+    ($head:expr, $($tail:expr),+ $(,)?) => {
+        $crate::sinks::MultiSink($head, multi_sink!($($tail),+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{
+        clock::Clock,
+        colors::*,
+        filter_maps::StaticChannelFilterMap,
+        log, debug, info, warning, error, critical,
+        loggers::{Logger, Stamping, ThreadInfo, ThreadName, single_threaded::SimpleLogger},
+    };
+
+    const TIME: u64 = 1_700_000_000;
+
+    fn log_to_string(f: impl FnOnce(&SimpleLogger<WriteSink<Vec<u8>>>)) -> String {
+        let logger = SimpleLogger::default().with_stamping(Stamping {
+            clock: Clock::Fixed(UNIX_EPOCH + Duration::from_secs(TIME)),
+            thread: None,
+        });
+        f(&logger);
+        String::from_utf8(logger.into_sink().output).unwrap()
+    }
+
+    fn test_log(setup: impl FnOnce(&SimpleLogger<WriteSink<Vec<u8>>>)) -> (u64, String) {
+        let output = log_to_string(|logger| {
+            setup(logger);
+            debug!(logger, "debug");
+            info!(logger, "info");
+            warning!(logger, "warning");
+            error!(logger, "error");
+            critical!(logger, "critical");
+            for i in 1..=10 {
+                log!(logger.channel(i), Level::DEBUG, "from channel {i}")
+            }
+        });
+        (TIME, output)
+    }
+
+    #[test]
+    fn test_colorless_idless() {
+        let (time, output) = test_log(|logger| {
+            logger.sink().theme = Theme::PLAIN;
+            logger.sink().thread_format = None;
+        });
+        let expected_output = format!(
+            "[{time}][DEBUG][0]: debug\n\
+             [{time}][INFO][0]: info\n\
+             [{time}][WARNING][0]: warning\n\
+             [{time}][ERROR][0]: error\n\
+             [{time}][CRITICAL][0]: critical\n\
+             [{time}][DEBUG][1]: from channel 1\n\
+             [{time}][DEBUG][2]: from channel 2\n\
+             [{time}][DEBUG][3]: from channel 3\n\
+             [{time}][DEBUG][4]: from channel 4\n\
+             [{time}][DEBUG][5]: from channel 5\n\
+             [{time}][DEBUG][6]: from channel 6\n\
+             [{time}][DEBUG][7]: from channel 7\n\
+             [{time}][DEBUG][8]: from channel 8\n\
+             [{time}][DEBUG][9]: from channel 9\n\
+             [{time}][DEBUG][10]: from channel 10\n"
+        );
+        assert_eq!(output, expected_output);
+    }
+
+	#[test]
+    fn test_colored_ided() {
+        let (time, output) = test_log(|logger| {
+            logger.sink().color_mode = ColorMode::Always;
+            logger.sink().theme = Theme::CLASSIC;
+            logger.sink().thread_format = Some(ThreadFormat::Id);
+        });
+        let id = ThreadInfo::current().id;
+        let expected_output = format!(
+            "[{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}0{RESET_COLOR}]: debug\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_BLUE}INFO{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}0{RESET_COLOR}]: info\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_YELLOW}WARNING{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}0{RESET_COLOR}]: warning\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_RED}ERROR{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}0{RESET_COLOR}]: error\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_MAGENTA}CRITICAL{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}0{RESET_COLOR}]: critical\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}1{RESET_COLOR}]: from channel 1\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}2{RESET_COLOR}]: from channel 2\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}3{RESET_COLOR}]: from channel 3\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}4{RESET_COLOR}]: from channel 4\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}5{RESET_COLOR}]: from channel 5\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}6{RESET_COLOR}]: from channel 6\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}7{RESET_COLOR}]: from channel 7\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}8{RESET_COLOR}]: from channel 8\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}9{RESET_COLOR}]: from channel 9\n\
+             [{SET_COLOR_BRIGHT_WHITE}{id}{RESET_COLOR}][{SET_COLOR_BRIGHT_GREEN}{time}{RESET_COLOR}][{SET_COLOR_BRIGHT_CYAN}DEBUG{RESET_COLOR}][{SET_COLOR_BRIGHT_WHITE}10{RESET_COLOR}]: from channel 10\n"
+        );
+        assert_eq!(output, expected_output);
+    }
+
+    #[derive(Debug, Default)]
+    struct FlushCounter {
+        flushes: usize,
+    }
+
+    impl Write for FlushCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush() {
+        let logger = SimpleLogger::new(WriteSink::from_write(FlushCounter::default(), InvisibleChannelFilterMap));
+        error!(logger, "error");
+        critical!(logger, "critical");
+        assert_eq!(logger.sink().output.flushes, 0);
+        logger.sink().flush_on_critical = true;
+        error!(logger, "error");
+        critical!(logger, "critical");
+        assert_eq!(logger.sink().output.flushes, 1);
+        {
+            let _guard = logger.flush_guard();
+            info!(logger.channel(1), "info");
+            assert_eq!(logger.sink().output.flushes, 1);
+        }
+        assert_eq!(logger.sink().output.flushes, 2);
+        let channel: &dyn Logger = &logger.channel(1);
+        channel.flush();
+        assert_eq!(logger.into_sink().output.flushes, 3);
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    /// A [Write] whose writes succeed but whose flushes fail.
+    #[derive(Clone, Copy, Debug, Default)]
+    struct UnflushableWriter;
+
+    impl Write for UnflushableWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let fallback = CaptureSink::new();
+        let logger = SimpleLogger::new(WriteSink::from_write(FailingWriter, InvisibleChannelFilterMap));
+        assert!(matches!(logger.sink().try_consume(LogObject::new(0, Level::INFO, format_args!("info"))), Err(SinkError::Io(_))));
+        assert_eq!(logger.sink().failed_writes, 1);
+        info!(logger, "info");
+        logger.flush();
+        assert_eq!(logger.sink().failed_writes, 3);
+        logger.sink().error_policy = ErrorPolicy::Ignore;
+        info!(logger, "info");
+        assert!(logger.sink().try_consume(LogObject::new(0, Level::INFO, format_args!("info"))).is_err());
+        assert_eq!(logger.sink().failed_writes, 3);
+        logger.sink().error_policy = ErrorPolicy::fallback(fallback.clone());
+        info!(logger, "fallback");
+        assert!(logger.sink().try_consume(LogObject::new(0, Level::INFO, format_args!("returned"))).is_err());
+        logger.flush();
+        assert_eq!(logger.sink().failed_writes, 6);
+        assert_eq!(fallback.take().iter().map(|record| record.message.as_str()).collect::<Vec<_>>(), ["fallback"]);
+        logger.sink().min_severity = Level::ERROR;
+        info!(logger, "filtered");
+        assert_eq!(logger.sink().failed_writes, 6);
+
+        let logger = SimpleLogger::new(WriteSink::from_write(UnflushableWriter, InvisibleChannelFilterMap));
+        logger.sink().error_policy = ErrorPolicy::fallback(fallback.clone());
+        logger.sink().flush_on_critical = true;
+        critical!(logger, "written but not flushed");
+        assert_eq!(logger.sink().failed_writes, 1);
+        assert!(fallback.is_empty());
+    }
+
+    #[test]
+    fn test_theme() {
+        const THEME: Theme = Theme {
+            channels: &[(2, Style::fg(Color::RED).underline())],
+            levels: [Style::NONE; 5],
+            time: Style::NONE,
+            ..Theme::PALETTE_256
+        };
+        let (time, output) = test_log(|logger| {
+            logger.sink().color_mode = ColorMode::Always;
+            logger.sink().theme = THEME;
+        });
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[0], format!("[{time}][DEBUG][\x1b[38;5;141m0{RESET_COLOR}]: debug"));
+        assert_eq!(lines[5], format!("[{time}][DEBUG][\x1b[38;5;208m1{RESET_COLOR}]: from channel 1"));
+        assert_eq!(lines[6], format!("[{time}][DEBUG][\x1b[4;31m2{RESET_COLOR}]: from channel 2"));
+        assert_eq!(lines[12], format!("[{time}][DEBUG][\x1b[38;5;203m8{RESET_COLOR}]: from channel 8"));
+    }
+
+    #[derive(Debug, Default)]
+    struct Terminal(Vec<u8>);
+
+    impl Write for Terminal {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogWrite for Terminal {
+        fn is_terminal(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_terminal_detection() {
+        let mut sink: WriteSink = Default::default();
+        assert_eq!(sink.colors(), ColorMode::Auto.enabled(std::io::IsTerminal::is_terminal(&std::io::stderr())));
+        let mut sink: WriteSink<Terminal> = Default::default();
+        assert_eq!(sink.colors(), ColorMode::Auto.enabled(true));
+        let mut sink = WriteSink::from_write(Terminal::default(), InvisibleChannelFilterMap);
+        assert_eq!(sink.colors(), ColorMode::Auto.enabled(false));
+    }
+
+    #[test]
+    fn test_color_mode() {
+        let mut sink: WriteSink<Vec<u8>> = Default::default();
+        assert_eq!(sink.colors(), ColorMode::Auto.enabled(false));
+        sink.is_terminal = |_| true;
+        sink.redetect_colors();
+        assert_eq!(sink.colors(), ColorMode::Auto.enabled(true));
+        sink.color_mode = ColorMode::Always;
+        assert!(sink.colors());
+        sink.color_mode = ColorMode::Never;
+        assert!(!sink.colors());
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("info")));
+        assert!(!String::from_utf8(sink.output).unwrap().contains('\x1b'));
+    }
+
+    #[test]
+    fn test_thread_format() {
+        let logger: crate::loggers::multi_threaded::SimpleLogger<WriteSink<Vec<u8>>> = Default::default();
+        logger.sink().unwrap().thread_format = Some(ThreadFormat::NameId);
+        std::thread::scope(|scope| {
+            std::thread::Builder::new().name("worker".into()).spawn_scoped(scope, || {
+                info!(logger, "named");
+            }).unwrap();
+        });
+        std::thread::scope(|scope| {
+            scope.spawn(|| info!(logger, "unnamed"));
+        });
+        let output = String::from_utf8(logger.into_sink().unwrap().output).unwrap();
+        let threads: Vec<_> = output.lines().map(|line| &line[1..line.find(']').unwrap()]).collect();
+        assert!(threads[0].starts_with("worker#"), "{}", threads[0]);
+        assert!(threads[1].parse::<u64>().is_ok(), "{}", threads[1]);
+    }
+
+    #[test]
+    fn test_multiline() {
+        let output = log_to_string(|logger| {
+            logger.sink().color_mode = ColorMode::Never;
+            logger.sink().multiline = MultilinePolicy::Indent;
+            info!(logger.channel(42), "first\nsecond");
+        });
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let indent = lines[0].find("first").unwrap();
+        assert!(lines[0].ends_with("][INFO][42]: first"));
+        assert_eq!(lines[1], format!("{:indent$}second", ""));
+    }
+
+    fn golden_logger(format: Format) -> SimpleLogger<WriteSink<Vec<u8>, StaticChannelFilterMap<&'static str, 2>>> {
+        let logger = SimpleLogger::new(WriteSink::new(Vec::new(), StaticChannelFilterMap(&["main", "net"]))).with_stamping(Stamping {
+            clock: Clock::Fixed(UNIX_EPOCH + Duration::from_millis(TIME * 1000 + 123)),
+            thread: Some(ThreadInfo { id: 7, name: Some(ThreadName::new("worker")), os_id: None }),
+        });
+        logger.sink().format = format;
+        logger.sink().thread_format = Some(ThreadFormat::Id);
+        logger
+    }
+
+    #[test]
+    fn test_logfmt() {
+        let logger = golden_logger(Format::Logfmt);
+        logger.sink().min_severity = Level::INFO;
+        debug!(logger, "filtered");
+        info!(logger, "started");
+        warning!(logger.channel(1), "timeout after {}s", 5);
+        error!(logger.channel(2), "filtered");
+        logger.sink().muted = true;
+        error!(logger, "muted");
+        let output = String::from_utf8(logger.into_sink().output).unwrap();
+        assert_eq!(
+            output,
+            "ts=2023-11-14T22:13:20.123000Z level=info channel=main thread=7 msg=started\n\
+             ts=2023-11-14T22:13:20.123000Z level=warning channel=net thread=7 msg=\"timeout after 5s\"\n",
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let logger = golden_logger(Format::Json);
+        info!(logger, "started");
+        warning!(logger.channel(1), "\"quoted\"\nsecond line");
+        error!(logger.channel(2), "filtered");
+        let output = String::from_utf8(logger.into_sink().output).unwrap();
+        assert_eq!(
+            output,
+            "{\"ts\":\"2023-11-14T22:13:20.123000Z\",\"level\":\"info\",\"channel\":\"main\",\"thread\":\"7\",\"msg\":\"started\"}\n\
+             {\"ts\":\"2023-11-14T22:13:20.123000Z\",\"level\":\"warning\",\"channel\":\"net\",\"thread\":\"7\",\"msg\":\"\\\"quoted\\\"\\nsecond line\"}\n",
+        );
+    }
+
+    #[test]
+    fn test_context() {
+        let mut outputs = Vec::new();
+        for format in [Format::Text, Format::Logfmt, Format::Json] {
+            let logger = golden_logger(format);
+            logger.sink().color_mode = ColorMode::Never;
+            logger.sink().thread_format = None;
+            logger.sink().multiline = MultilinePolicy::Indent;
+            let _request = crate::context::push("request_id", 42);
+            let _user = crate::context::push("user", "a b");
+            let _msg = crate::context::push("msg", "x\ny");
+            let _key = crate::context::push("a=b", "\x1b[2J");
+            info!(logger, "handled");
+            outputs.push(String::from_utf8(logger.into_sink().output).unwrap());
+        }
+        assert_eq!(outputs[0], format!("[{TIME}][INFO][main][request_id=42 user=a b msg=x\\ny a=b=\\x1b[2J]: handled\n"));
+        assert_eq!(
+            outputs[1],
+            "ts=2023-11-14T22:13:20.123000Z level=info channel=main request_id=42 user=\"a b\" context_msg=\"x\\ny\" a_b=\"\\u001b[2J\" msg=handled\n",
+        );
+        assert_eq!(
+            outputs[2],
+            "{\"ts\":\"2023-11-14T22:13:20.123000Z\",\"level\":\"info\",\"channel\":\"main\",\"request_id\":\"42\",\"user\":\"a b\",\
+             \"context_msg\":\"x\\ny\",\"a=b\":\"\\u001b[2J\",\"msg\":\"handled\"}\n",
+        );
+    }
+
+    #[test]
+    fn test_thread_override() {
+        let logger = golden_logger(Format::Text);
+        logger.sink().color_mode = ColorMode::Never;
+        logger.sink().thread_format = Some(ThreadFormat::NameId);
+        info!(logger.channel(1), "overridden");
+        let output = String::from_utf8(logger.into_sink().output).unwrap();
+        assert_eq!(output, format!("[worker#7][{TIME}][INFO][net]: overridden\n"));
+    }
+}
@@ -0,0 +1,170 @@
+//! [MultilinePolicy] for messages spanning multiple lines.
+
+use std::fmt::{Display, Write};
+
+/// How a [WriteSink](super::WriteSink) handles messages spanning multiple lines.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum MultilinePolicy {
+    /// Messages are written as they are.
+    #[default]
+    Raw,
+    /// Continuation lines are indented to line up with the first line of the message.
+    ///
+    /// Control characters other than newlines and tabs are escaped as under [MultilinePolicy::Escape],
+    /// empty lines aren't indented and trailing newlines are dropped.
+    Indent,
+    /// Continuation lines are prefixed with the header of the first line.
+    ///
+    /// Control characters other than newlines and tabs are escaped as under [MultilinePolicy::Escape]
+    /// and trailing newlines are dropped.
+    RepeatHeader,
+    /// Newlines and other control characters (including the escape character starting ANSI sequences)
+    /// are escaped (e.g. as `\n` or `\x1b`) so that every record is a single line.
+    Escape,
+}
+
+/// Displays a message preceded by a header according to a [MultilinePolicy].
+pub(crate) struct Record<H: Display, M: Display> {
+    pub header: H,
+    pub message: M,
+    pub policy: MultilinePolicy,
+}
+
+impl<H: Display, M: Display> Display for Record<H, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.header.fmt(f)?;
+        match self.policy {
+            MultilinePolicy::Raw => self.message.fmt(f),
+            policy => write!(Lines { f, header: &self.header, indent: None, newlines: 0, policy }, "{}", self.message),
+        }
+    }
+}
+
+struct Lines<'a, 'b, H: Display> {
+    f: &'a mut std::fmt::Formatter<'b>,
+    header: &'a H,
+    indent: Option<usize>,
+    newlines: usize,
+    policy: MultilinePolicy,
+}
+
+/// Writes `c`, escaping it if it's a control character (like [MultilinePolicy::Escape]).
+fn escape(f: &mut impl Write, c: char) -> std::fmt::Result {
+    match c {
+        '\n' => f.write_str("\\n"),
+        '\r' => f.write_str("\\r"),
+        '\t' => f.write_str("\\t"),
+        c if c.is_control() => write!(f, "\\x{:02x}", c as u32),
+        c => f.write_char(c),
+    }
+}
+
+/// Displays a value with control characters escaped (like [MultilinePolicy::Escape]).
+pub(crate) struct Escaped<T: Display>(pub T);
+
+impl<T: Display> Display for Escaped<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Escaper<'a, 'b>(&'a mut std::fmt::Formatter<'b>);
+
+        impl Write for Escaper<'_, '_> {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                s.chars().try_for_each(|c| escape(self.0, c))
+            }
+        }
+
+        write!(Escaper(f), "{}", self.0)
+    }
+}
+
+impl<H: Display> Lines<'_, '_, H> {
+    /// Writes the newlines held back until the next character (so that trailing ones are dropped).
+    fn write_newlines(&mut self) -> std::fmt::Result {
+        while self.newlines > 0 {
+            self.newlines -= 1;
+            self.f.write_char('\n')?;
+            match (self.policy, self.newlines) {
+                (MultilinePolicy::Indent, 0) => self.indent()?,
+                (MultilinePolicy::Indent, _) => (),
+                _ => self.header.fmt(self.f)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self) -> std::fmt::Result {
+        let indent = *self.indent.get_or_insert_with(|| {
+            let mut width = VisibleWidth::default();
+            let _ = write!(width, "{}", self.header);
+            width.width
+        });
+        write!(self.f, "{:indent$}", "")
+    }
+}
+
+impl<H: Display> Write for Lines<'_, '_, H> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for c in s.chars() {
+            match (self.policy, c) {
+                (MultilinePolicy::Escape, c) => escape(self.f, c)?,
+                (_, '\n') => self.newlines += 1,
+                (_, '\t') => {
+                    self.write_newlines()?;
+                    self.f.write_char('\t')?;
+                },
+                (_, c) => {
+                    self.write_newlines()?;
+                    escape(self.f, c)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the characters that aren't part of ANSI escape-sequences.
+#[derive(Debug, Default)]
+struct VisibleWidth {
+    escaped: bool,
+    width: usize,
+}
+
+impl Write for VisibleWidth {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for c in s.chars() {
+            match (self.escaped, c) {
+                (false, '\x1b') => self.escaped = true,
+                (false, _) => self.width += 1,
+                (true, c) => self.escaped = !c.is_ascii_alphabetic(),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(policy: MultilinePolicy, message: &str) -> String {
+        Record { header: "\x1b[1;31m[ERROR]\x1b[0m: ", message, policy }.to_string()
+    }
+
+    #[test]
+    fn test_policies() {
+        let message = "error chain:\n  caused by: \x1b[2Jtimeout\n";
+        assert_eq!(record(MultilinePolicy::Raw, message), format!("\x1b[1;31m[ERROR]\x1b[0m: {message}"));
+        assert_eq!(
+            record(MultilinePolicy::Indent, message),
+            "\x1b[1;31m[ERROR]\x1b[0m: error chain:\n           caused by: \\x1b[2Jtimeout",
+        );
+        assert_eq!(record(MultilinePolicy::Indent, "a\n\n\tb\r"), "\x1b[1;31m[ERROR]\x1b[0m: a\n\n         \tb\\r");
+        assert_eq!(
+            record(MultilinePolicy::RepeatHeader, "a\nb\x1b[2J\n"),
+            "\x1b[1;31m[ERROR]\x1b[0m: a\n\x1b[1;31m[ERROR]\x1b[0m: b\\x1b[2J",
+        );
+        assert_eq!(
+            record(MultilinePolicy::Escape, message),
+            "\x1b[1;31m[ERROR]\x1b[0m: error chain:\\n  caused by: \\x1b[2Jtimeout\\n",
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! [NetworkSink] for streaming formatted [LogObject]s to a log aggregator.
+
+use std::{
+    collections::VecDeque,
+    io::{Error, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::PathBuf};
+
+use crate::{
+    colors::ColorMode,
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{Level, LogObject},
+    sinks::{Sink, SinkError, WriteSink, synthesize},
+    writers::Write,
+};
+
+/// Where a [NetworkSink] connects to.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum NetworkAddress {
+    /// A TCP address like `localhost:5170`, resolved on every connection attempt.
+    Tcp(String),
+    /// The path of a Unix stream socket.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// How a [NetworkSink] delimits records.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Framing {
+    /// Every record is terminated by a newline.
+    ///
+    /// Requires a single-line format (e.g. [Format::Json](super::Format::Json)).
+    #[default]
+    Newline,
+    /// Every record (without its trailing newline) is preceded by its length as a big-endian [u32].
+    LengthPrefixed,
+}
+
+#[derive(Debug)]
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn open(address: &NetworkAddress, timeout: Duration) -> std::io::Result<Self> {
+        let connection = match address {
+            NetworkAddress::Tcp(address) => {
+                let mut last_error = Error::from(ErrorKind::NotFound);
+                let mut stream = None;
+                for address in address.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&address, timeout) {
+                        Ok(s) => {
+                            stream = Some(s);
+                            break;
+                        },
+                        Err(e) => last_error = e,
+                    }
+                }
+                let stream = stream.ok_or(last_error)?;
+                stream.set_write_timeout(Some(timeout))?;
+                Connection::Tcp(stream)
+            },
+            #[cfg(unix)]
+            NetworkAddress::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_write_timeout(Some(timeout))?;
+                Connection::Unix(stream)
+            },
+        };
+        Ok(connection)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write_all(buf),
+        }
+    }
+}
+
+/// Connects a [UdpSocket] bound to the unspecified address of the same family as the resolved `address`.
+pub(crate) fn connect_udp(address: impl ToSocketAddrs) -> std::io::Result<UdpSocket> {
+    let mut last_error = Error::from(ErrorKind::NotFound);
+    for address in address.to_socket_addrs()? {
+        let unspecified: IpAddr = match address {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        match UdpSocket::bind((unspecified, 0)).and_then(|socket| socket.connect(address).map(|()| socket)) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// A [Sink] that streams [LogObject]s formatted by a [WriteSink] to a log aggregator over TCP or a Unix socket.
+///
+/// The connection is established lazily and re-established with exponential backoff
+/// (from [NetworkSink::initial_backoff] up to [NetworkSink::max_backoff]) after failures.
+/// While disconnected, records are buffered up to [NetworkSink::buffer_limit] bytes; further records are dropped.
+/// After reconnecting, a [Level::WARNING] record reporting the number of dropped records is sent on channel 0.
+/// When dropped, a final reconnection attempt (regardless of the backoff) is made to send the buffered records.
+#[derive(Debug)]
+pub struct NetworkSink<M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// Where to connect to.
+    pub address: NetworkAddress,
+    /// The maximum number of bytes buffered while disconnected.
+    pub buffer_limit: usize,
+    /// The [WriteSink] formatting (and filtering) [LogObject]s. Its output is taken after every record.
+    pub formatter: WriteSink<Vec<u8>, M>,
+    /// How records are delimited.
+    pub framing: Framing,
+    /// The backoff before the first reconnection attempt after a failure.
+    pub initial_backoff: Duration,
+    /// The maximum backoff between reconnection attempts.
+    pub max_backoff: Duration,
+    /// The timeout for connecting and for writing a record.
+    pub timeout: Duration,
+    buffer: VecDeque<Vec<u8>>,
+    buffered_bytes: usize,
+    connection: Option<Connection>,
+    dropped: u64,
+    unreported_drops: u64,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl<M: ChannelFilterMap> NetworkSink<M> {
+    /// Constructs a new [NetworkSink] with default settings (that shouldn't be relied upon).
+    ///
+    /// No connection is attempted before the first [LogObject] is consumed.
+    #[must_use]
+    pub const fn new(address: NetworkAddress, channel_map: M) -> Self {
+        let mut formatter = WriteSink::new(Vec::new(), channel_map);
+        formatter.color_mode = ColorMode::Never;
+        Self {
+            address,
+            buffer_limit: 1 << 20,
+            formatter,
+            framing: Framing::Newline,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            timeout: Duration::from_secs(5),
+            buffer: VecDeque::new(),
+            buffered_bytes: 0,
+            connection: None,
+            dropped: 0,
+            unreported_drops: 0,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Constructs a new [NetworkSink] connecting to a TCP address like `localhost:5170`.
+    #[must_use]
+    pub fn tcp(address: impl Into<String>, channel_map: M) -> Self {
+        Self::new(NetworkAddress::Tcp(address.into()), channel_map)
+    }
+
+    /// Constructs a new [NetworkSink] connecting to a Unix stream socket.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn unix(path: impl Into<PathBuf>, channel_map: M) -> Self {
+        Self::new(NetworkAddress::Unix(path.into()), channel_map)
+    }
+
+    /// Returns the total number of records dropped because the buffer was full.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the number of bytes currently buffered.
+    #[must_use]
+    pub const fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Returns whether the sink is currently connected.
+    #[must_use]
+    pub const fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Takes and frames the formatter's output, returning [None] if it's empty (i.e. the record was filtered).
+    ///
+    /// Fails if the record is too long for [Framing::LengthPrefixed].
+    fn take_record(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        let mut record = std::mem::take(&mut self.formatter.output);
+        if record.is_empty() {
+            return Ok(None);
+        }
+        if let Framing::LengthPrefixed = self.framing {
+            if record.last() == Some(&b'\n') {
+                record.pop();
+            }
+            let len = u32::try_from(record.len()).map_err(|_| Error::new(ErrorKind::InvalidInput, "record is too long for its length prefix"))?;
+            record.splice(0..0, len.to_be_bytes());
+        }
+        Ok(Some(record))
+    }
+
+    /// Buffers a record, returning whether there was room (which is only limited while disconnected).
+    ///
+    /// The report of dropped records is stamped like `trigger` (if any).
+    fn enqueue(&mut self, record: Vec<u8>, trigger: Option<&LogObject>) -> bool {
+        if !self.connect(trigger) && self.buffered_bytes + record.len() > self.buffer_limit {
+            self.dropped += 1;
+            self.unreported_drops += 1;
+            return false;
+        }
+        self.buffered_bytes += record.len();
+        self.buffer.push_back(record);
+        true
+    }
+
+    /// Connects if disconnected and the backoff has elapsed, returning whether the sink is connected.
+    ///
+    /// The report of dropped records is stamped like `trigger` (if any).
+    fn connect(&mut self, trigger: Option<&LogObject>) -> bool {
+        if self.connection.is_some() {
+            return true;
+        }
+        if self.retry_at.is_some_and(|retry_at| Instant::now() < retry_at) {
+            return false;
+        }
+        match Connection::open(&self.address, self.timeout) {
+            Ok(connection) => {
+                self.connection = Some(connection);
+                self.retry_at = None;
+                self.failures = 0;
+                if self.unreported_drops > 0 {
+                    let _ = self.formatter.try_consume(synthesize(
+                        trigger,
+                        0,
+                        Level::WARNING,
+                        format_args!("dropped {} records while disconnected", self.unreported_drops),
+                    ));
+                    self.unreported_drops = 0;
+                    // the report is sent first (regardless of the limit) as the dropped records preceded the buffered ones
+                    if let Ok(Some(record)) = self.take_record() {
+                        self.buffered_bytes += record.len();
+                        self.buffer.push_front(record);
+                    }
+                }
+                true
+            },
+            Err(_) => {
+                self.disconnect();
+                false
+            },
+        }
+    }
+
+    fn disconnect(&mut self) {
+        self.connection = None;
+        let backoff = self.initial_backoff.saturating_mul(1 << self.failures.min(31));
+        self.retry_at = Some(Instant::now() + backoff.min(self.max_backoff));
+        self.failures += 1;
+    }
+
+    /// Sends buffered records (if connected or able to reconnect).
+    fn send_buffered(&mut self, trigger: Option<&LogObject>) -> std::io::Result<()> {
+        while !self.buffer.is_empty() && self.connect(trigger) {
+            let connection = self.connection.as_mut().expect("connected");
+            let record = self.buffer.front().expect("not empty");
+            if let Err(e) = connection.write_all(record) {
+                self.disconnect();
+                return Err(e);
+            }
+            self.buffered_bytes -= record.len();
+            self.buffer.pop_front();
+        }
+        Ok(())
+    }
+}
+
+impl<M: ChannelFilterMap> Sink for NetworkSink<M> {
+    fn consume(&mut self, log_object: LogObject) {
+        let _ = self.try_consume(log_object);
+    }
+
+    /// Fails only if the record had to be dropped or is too long for [Framing::LengthPrefixed].
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.formatter.try_consume(log_object)?;
+        let buffered = match self.take_record()? {
+            Some(record) => self.enqueue(record, Some(&log_object)),
+            None => true,
+        };
+        let sent = self.send_buffered(Some(&log_object));
+        match (buffered, sent) {
+            (true, _) => Ok(()),
+            (false, Err(e)) => Err(e.into()),
+            (false, Ok(())) => Err(Error::other("network buffer is full").into()),
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.send_buffered(None);
+    }
+}
+
+impl<M: ChannelFilterMap> Drop for NetworkSink<M> {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            self.retry_at = None;
+            let _ = self.send_buffered(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::{
+        debug, info,
+        filter_maps::StaticChannelFilterMap,
+        loggers::{Logger, single_threaded::SimpleLogger},
+        sinks::Format,
+    };
+
+    #[test]
+    fn test_newline() {
+        let aggregator = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let sink = NetworkSink::tcp(aggregator.local_addr().unwrap().to_string(), StaticChannelFilterMap(&["main", "net"]));
+        let logger = SimpleLogger::new(sink);
+        logger.sink().formatter.format = Format::Logfmt;
+        logger.sink().buffer_limit = 0;
+        info!(logger, "started");
+        debug!(logger.channel(1), "connected");
+        debug!(logger.channel(2), "filtered");
+        assert!(logger.sink().is_connected());
+        assert_eq!(logger.sink().dropped(), 0);
+        let (stream, _) = aggregator.accept().unwrap();
+        drop(logger);
+        let lines: Vec<_> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("level=info channel=main msg=started"));
+        assert!(lines[1].ends_with("level=debug channel=net msg=connected"));
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let address = TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap();
+        let mut sink = NetworkSink::tcp(address.to_string(), InvisibleChannelFilterMap);
+        sink.formatter.format = Format::Json;
+        sink.framing = Framing::LengthPrefixed;
+        sink.initial_backoff = Duration::ZERO;
+        sink.buffer_limit = 200;
+        for i in 0..5 {
+            sink.consume(LogObject::new(1, Level::INFO, format_args!("buffered {i}")));
+        }
+        assert!(!sink.is_connected());
+        assert_eq!(sink.dropped(), 3);
+        assert!(sink.buffered_bytes() <= 200);
+        let aggregator = TcpListener::bind(address).unwrap();
+        sink.flush();
+        assert!(sink.is_connected());
+        assert_eq!(sink.buffered_bytes(), 0);
+        sink.consume(LogObject::new(1, Level::INFO, format_args!("live")));
+        let (mut stream, _) = aggregator.accept().unwrap();
+        drop(sink);
+        let mut messages = Vec::new();
+        let mut len = [0; 4];
+        while stream.read_exact(&mut len).is_ok() {
+            let mut record = vec![0; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut record).unwrap();
+            let record = String::from_utf8(record).unwrap();
+            assert!(record.starts_with('{') && record.ends_with('}'));
+            messages.push(record.split_once("\"msg\":").unwrap().1.to_owned());
+        }
+        assert_eq!(messages, [
+            "\"dropped 3 records while disconnected\"}",
+            "\"buffered 0\"}",
+            "\"buffered 1\"}",
+            "\"live\"}",
+        ]);
+    }
+
+    #[test]
+    fn test_drop() {
+        let address = TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap();
+        let mut sink = NetworkSink::tcp(address.to_string(), InvisibleChannelFilterMap);
+        sink.formatter.format = Format::Logfmt;
+        sink.initial_backoff = Duration::from_secs(3600);
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("buffered")));
+        let aggregator = TcpListener::bind(address).unwrap();
+        sink.flush();
+        assert!(!sink.is_connected());
+        drop(sink);
+        let (stream, _) = aggregator.accept().unwrap();
+        let lines: Vec<_> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("msg=buffered"));
+    }
+}
@@ -0,0 +1,562 @@
+//! [OtlpSink] for exporting [LogObject]s to an OpenTelemetry collector.
+
+use std::{
+    fmt::Write as _,
+    io::{Error, ErrorKind, Read},
+    net::TcpStream,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use crate::{
+    context::Context,
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{Level, LogObject, LogRecord},
+    sinks::{ErrorPolicy, Sink, SinkError, json, process},
+    writers::Write,
+};
+
+/// The trace and span a [LogObject] was logged in.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The span id.
+    pub span_id: [u8; 8],
+    /// The trace id.
+    pub trace_id: [u8; 16],
+}
+
+impl TraceContext {
+    /// The [Context] keys of the trace id and span id read by [TraceContext::from_context()].
+    pub const KEYS: [&str; 2] = ["trace_id", "span_id"];
+
+    /// Parses a trace id of 32 and a span id of 16 hexadecimal digits.
+    #[must_use]
+    pub fn from_hex(trace_id: &str, span_id: &str) -> Option<Self> {
+        Some(Self { span_id: decode_hex(span_id)?, trace_id: decode_hex(trace_id)? })
+    }
+
+    /// Parses the innermost `trace_id` and `span_id` of the [Context] (if both are present and valid).
+    #[must_use]
+    pub fn from_context(context: Context) -> Option<Self> {
+        Self::from_hex(context.get(Self::KEYS[0])?, context.get(Self::KEYS[1])?)
+    }
+}
+
+/// Returns the OpenTelemetry `SeverityNumber` of the level.
+#[must_use]
+pub const fn otlp_severity(level: Level) -> u8 {
+    match level {
+        Level::DEBUG    => 5,
+        Level::INFO     => 9,
+        Level::WARNING  => 13,
+        Level::ERROR    => 17,
+        Level::CRITICAL => 21,
+    }
+}
+
+/// A [Sink] that exports [LogObject]s via a [ChannelFilterMap] to an OpenTelemetry collector using OTLP/HTTP with JSON encoding.
+///
+/// [LogObject]s are batched and exported when [OtlpSink::max_batch_size] is reached,
+/// when a [LogObject] is consumed after [OtlpSink::batch_interval] has elapsed, on [Sink::flush()] and on drop.
+/// Failed exports are retried [OtlpSink::max_retries] times with exponential backoff by later calls instead of blocking the logging thread
+/// (new [LogObject]s are batched in the meantime). Only on drop the sink waits for the retries.
+/// If an export finally fails, its [LogObject]s are passed to [ErrorPolicy::Fallback] (if that's the policy).
+/// Only plain `http://` endpoints are supported.
+///
+/// The [LogObject::context] is exported as string attributes
+/// (prefixed with `context.` if they clash with the other attributes, e.g. `context.thread.id`)
+/// except for a valid `trace_id` and `span_id` (see [TraceContext::from_context()]), which are exported as `traceId` and `spanId`.
+#[derive(Debug)]
+pub struct OtlpSink<M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// The maximum age of a batch before it's exported.
+    pub batch_interval: Duration,
+    /// The [ChannelFilterMap] used for the `logidize.channel` attribute.
+    pub channel_map: M,
+    /// The URL [OtlpSink] POSTs to like `http://localhost:4318/v1/logs`.
+    pub endpoint: String,
+    /// How failed exports are handled by [Sink::consume()] and [Sink::flush()].
+    pub error_policy: ErrorPolicy,
+    /// The number of failed exports that weren't ignored according to [OtlpSink::error_policy].
+    pub failed_exports: u64,
+    /// Additional HTTP headers (e.g. for authentication).
+    pub headers: Vec<(String, String)>,
+    /// The maximum number of [LogObject]s per export.
+    pub max_batch_size: usize,
+    /// The number of retries of a failed export.
+    pub max_retries: u32,
+    /// The sink's minimum severity level. [OtlpSink] won't log [LogObject]s of lower severity.
+    pub min_severity: Level,
+    /// Whether the sink is muted. A muted [OtlpSink] won't log anything.
+    pub muted: bool,
+    /// The resource's attributes (by default `service.name` and `host.name`).
+    pub resource_attributes: Vec<(String, String)>,
+    /// The backoff before the first retry of a failed export.
+    pub retry_backoff: Duration,
+    /// The timeout for connecting, sending and receiving the response.
+    pub timeout: Duration,
+    /// Returns a [TraceContext] overriding the one in [LogObject::context] (if any).
+    ///
+    /// Returns [None] by default.
+    pub trace_context: fn() -> Option<TraceContext>,
+    batch: Vec<String>,
+    batch_records: Vec<LogRecord>,
+    batch_started: Option<Instant>,
+    dropped: u64,
+    failed_records: Vec<LogRecord>,
+    pending: Option<PendingExport>,
+    reported: bool,
+}
+
+/// An export waiting to be retried.
+#[derive(Debug)]
+struct PendingExport {
+    payload: String,
+    /// The exported [LogRecord]s (only kept for [ErrorPolicy::Fallback]).
+    records: Vec<LogRecord>,
+    len: u64,
+    retries: u32,
+    retry_at: Instant,
+}
+
+impl<M: ChannelFilterMap> OtlpSink<M> {
+    /// Constructs a new [OtlpSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub fn new(endpoint: impl Into<String>, channel_map: M) -> Self {
+        let mut resource_attributes = vec![("service.name".into(), process::process_name().unwrap_or_else(|| "unknown_service".into()))];
+        if let Some(hostname) = process::hostname() {
+            resource_attributes.push(("host.name".into(), hostname));
+        }
+        Self {
+            batch_interval: Duration::from_secs(5),
+            channel_map,
+            endpoint: endpoint.into(),
+            error_policy: ErrorPolicy::Count,
+            failed_exports: 0,
+            headers: Vec::new(),
+            max_batch_size: 512,
+            max_retries: 3,
+            min_severity: Level::DEBUG,
+            muted: false,
+            resource_attributes,
+            retry_backoff: Duration::from_millis(100),
+            timeout: Duration::from_secs(10),
+            trace_context: || None,
+            batch: Vec::new(),
+            batch_records: Vec::new(),
+            batch_started: None,
+            dropped: 0,
+            failed_records: Vec::new(),
+            pending: None,
+            reported: false,
+        }
+    }
+
+    /// Returns the number of [LogObject]s dropped because their export failed.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the number of [LogObject]s waiting to be exported (excluding a failed export waiting to be retried).
+    #[must_use]
+    pub fn batched(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Returns whether a failed export is waiting to be retried.
+    #[must_use]
+    pub const fn is_retrying(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Retries a failed export if its backoff has elapsed and then exports all batched [LogObject]s unless the retry is still pending.
+    ///
+    /// Never sleeps, a failed export is retried by a later call according to [OtlpSink::max_retries].
+    pub fn export(&mut self) -> Result<(), SinkError> {
+        self.retry()?;
+        if self.pending.is_some() || self.batch.is_empty() {
+            return Ok(());
+        }
+        self.pending = Some(PendingExport {
+            payload: self.payload(),
+            records: std::mem::take(&mut self.batch_records),
+            len: self.batch.len() as u64,
+            retries: 0,
+            retry_at: Instant::now(),
+        });
+        self.batch.clear();
+        self.batch_started = None;
+        self.retry()
+    }
+
+    /// Attempts the pending export if its backoff has elapsed.
+    fn retry(&mut self) -> Result<(), SinkError> {
+        let Some(mut export) = self.pending.take() else {
+            return Ok(());
+        };
+        if Instant::now() < export.retry_at {
+            self.pending = Some(export);
+            return Ok(());
+        }
+        match self.post(&export.payload) {
+            Ok(()) => Ok(()),
+            Err((_, true)) if export.retries < self.max_retries => {
+                export.retry_at = Instant::now() + self.retry_backoff.saturating_mul(1 << export.retries.min(31));
+                export.retries += 1;
+                self.pending = Some(export);
+                Ok(())
+            },
+            Err((error, _)) => {
+                self.dropped += export.len;
+                self.failed_records = export.records;
+                Err(error.into())
+            },
+        }
+    }
+
+    /// Handles a failed export according to [OtlpSink::error_policy], passing its [LogObject]s to [ErrorPolicy::Fallback].
+    fn handle(&mut self, error: SinkError) {
+        self.error_policy.handle(&mut self.failed_exports, &mut self.reported, error, None);
+        for record in std::mem::take(&mut self.failed_records) {
+            record.with_log_object(|log_object| self.error_policy.fall_back(log_object));
+        }
+    }
+
+    /// Encodes a [LogObject] as an OTLP `LogRecord`.
+    fn encode(&self, log_object: &LogObject, channel: impl std::fmt::Display) -> String {
+        let time = log_object.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let observed = std::time::SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let mut record = format!(
+            "{{\"timeUnixNano\":\"{time}\",\"observedTimeUnixNano\":\"{observed}\",\"severityNumber\":{},\"severityText\":\"{}\",\"body\":{{\"stringValue\":",
+            otlp_severity(log_object.severity),
+            log_object.severity.as_str(),
+        );
+        json::push_string(&mut record, log_object.message);
+        record.push_str("},\"attributes\":[");
+        push_attribute(&mut record, "logidize.channel", channel);
+        record.push(',');
+        push_int_attribute(&mut record, "thread.id", log_object.thread.id);
+        if let Some(name) = log_object.thread.name {
+            record.push(',');
+            push_attribute(&mut record, "thread.name", name);
+        }
+        record.push(',');
+        push_attribute(&mut record, "code.filepath", log_object.location.file());
+        record.push(',');
+        push_int_attribute(&mut record, "code.lineno", log_object.location.line().into());
+        let context_trace = TraceContext::from_context(log_object.context);
+        for (key, value) in log_object.context.iter() {
+            if context_trace.is_some() && TraceContext::KEYS.contains(&key) {
+                continue;
+            }
+            record.push(',');
+            match RESERVED_ATTRIBUTES.contains(&key) {
+                true => push_attribute(&mut record, &format!("context.{key}"), value),
+                false => push_attribute(&mut record, key, value),
+            }
+        }
+        record.push(']');
+        if let Some(TraceContext { span_id, trace_id }) = (self.trace_context)().or(context_trace) {
+            let _ = write!(record, ",\"traceId\":\"{}\",\"spanId\":\"{}\"", Hex(&trace_id), Hex(&span_id));
+        }
+        record.push('}');
+        record
+    }
+
+    /// Returns the `ExportLogsServiceRequest` for the batch.
+    fn payload(&self) -> String {
+        let mut payload = String::from("{\"resourceLogs\":[{\"resource\":{\"attributes\":[");
+        for (i, (key, value)) in self.resource_attributes.iter().enumerate() {
+            if i > 0 {
+                payload.push(',');
+            }
+            push_attribute(&mut payload, key, value);
+        }
+        payload.push_str("]},\"scopeLogs\":[{\"scope\":{\"name\":\"logidize\",\"version\":\"");
+        payload.push_str(env!("CARGO_PKG_VERSION"));
+        payload.push_str("\"},\"logRecords\":[");
+        payload.push_str(&self.batch.join(","));
+        payload.push_str("]}]}]}");
+        payload
+    }
+
+    /// POSTs the payload, returning the error and whether it's worth retrying on failure.
+    fn post(&self, payload: &str) -> Result<(), (Error, bool)> {
+        let url = self.endpoint.strip_prefix("http://")
+            .ok_or_else(|| (Error::new(ErrorKind::InvalidInput, "OTLP endpoint must be an http:// URL"), false))?;
+        let (authority, path) = url.find('/').map_or((url, "/"), |i| url.split_at(i));
+        let connect = || {
+            let address = std::net::ToSocketAddrs::to_socket_addrs(authority)?.next().ok_or(ErrorKind::NotFound)?;
+            let stream = TcpStream::connect_timeout(&address, self.timeout)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            Ok::<_, Error>(stream)
+        };
+        let mut stream = connect().map_err(|e| (e, true))?;
+        let mut request = format!(
+            "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            payload.len(),
+        );
+        for (name, value) in &self.headers {
+            let _ = write!(request, "{name}: {value}\r\n");
+        }
+        request.push_str("\r\n");
+        request.push_str(payload);
+        stream.write_all(request.as_bytes()).map_err(|e| (e, true))?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(|e| (e, true))?;
+        let status = std::str::from_utf8(&response).ok()
+            .and_then(|response| response.split(' ').nth(1))
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| (Error::new(ErrorKind::InvalidData, "invalid HTTP response from OTLP endpoint"), true))?;
+        match status {
+            200..=299 => Ok(()),
+            _ => Err((Error::other(format!("OTLP endpoint responded with status {status}")), matches!(status, 429 | 502..=504))),
+        }
+    }
+}
+
+impl<M: ChannelFilterMap> Sink for OtlpSink<M> {
+    fn consume(&mut self, log_object: LogObject) {
+        if let Err(error) = self.try_consume(log_object) {
+            self.handle(error);
+        }
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        if self.muted || log_object.severity < self.min_severity {
+            return Ok(());
+        }
+        let Some(channel_name) = self.channel_map.filter_map(&log_object) else {
+            return Ok(());
+        };
+        let record = self.encode(&log_object, channel_name);
+        self.batch.push(record);
+        if let ErrorPolicy::Fallback(_) = self.error_policy {
+            self.batch_records.push(log_object.to_record());
+        }
+        let batch_started = *self.batch_started.get_or_insert_with(Instant::now);
+        if self.pending.is_some() || self.batch.len() >= self.max_batch_size || batch_started.elapsed() >= self.batch_interval {
+            self.export()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        if let Err(error) = self.export() {
+            self.handle(error);
+        }
+    }
+}
+
+/// Waits for the retries of failed exports.
+impl<M: ChannelFilterMap> Drop for OtlpSink<M> {
+    fn drop(&mut self) {
+        self.flush();
+        while let Some(export) = &self.pending {
+            std::thread::sleep(export.retry_at.saturating_duration_since(Instant::now()));
+            self.flush();
+        }
+    }
+}
+
+/// The attributes exported for every [LogObject], which context keys mustn't override.
+const RESERVED_ATTRIBUTES: [&str; 5] = ["logidize.channel", "thread.id", "thread.name", "code.filepath", "code.lineno"];
+
+/// Appends an OTLP `KeyValue` with a string value.
+fn push_attribute(json: &mut String, key: &str, value: impl std::fmt::Display) {
+    json.push_str("{\"key\":");
+    json::push_string(json, key);
+    json.push_str(",\"value\":{\"stringValue\":");
+    json::push_string(json, value);
+    json.push_str("}}");
+}
+
+/// Appends an OTLP `KeyValue` with an integer value.
+fn push_int_attribute(json: &mut String, key: &str, value: u64) {
+    json.push_str("{\"key\":");
+    json::push_string(json, key);
+    let _ = write!(json, ",\"value\":{{\"intValue\":\"{value}\"}}}}");
+}
+
+/// Parses exactly `2 * N` hexadecimal digits.
+fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != 2 * N {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let digit = |digit: u8| char::from(digit).to_digit(16);
+        *byte = (digit(digits[0])? << 4 | digit(digits[1])?) as u8;
+    }
+    Some(bytes)
+}
+
+/// Displays bytes as lowercase hexadecimal.
+struct Hex<'a>(&'a [u8]);
+
+impl std::fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::{
+        filter_maps::StaticChannelFilterMap,
+        info, warning,
+        loggers::{Logger, single_threaded::SimpleLogger},
+        sinks::CaptureSink,
+    };
+
+    /// Starts an HTTP stand-in responding with the statuses in order and sending the received bodies.
+    fn collector(statuses: &'static [u16]) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let endpoint = format!("http://{}/v1/logs", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for &status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                assert_eq!(line, "POST /v1/logs HTTP/1.1\r\n");
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(length) = line.strip_prefix("Content-Length: ") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                    if line.starts_with("Authorization: ") {
+                        assert_eq!(line, "Authorization: Bearer token\r\n");
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                write!(reader.get_mut(), "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\n\r\n").unwrap();
+                sender.send(String::from_utf8(body).unwrap()).unwrap();
+            }
+        });
+        (endpoint, receiver)
+    }
+
+    #[test]
+    fn test_export() {
+        let (endpoint, bodies) = collector(&[503, 200, 200]);
+        let mut sink = OtlpSink::new(endpoint, StaticChannelFilterMap(&["main", "net"]));
+        sink.headers.push(("Authorization".into(), "Bearer token".into()));
+        sink.max_batch_size = 2;
+        sink.resource_attributes = vec![("service.name".into(), "app".into()), ("host.name".into(), "host".into())];
+        sink.retry_backoff = Duration::from_millis(1);
+        sink.trace_context = || Some(TraceContext { span_id: [0xab; 8], trace_id: [1; 16] });
+        let logger = SimpleLogger::new(sink);
+        info!(logger, "started");
+        {
+            let _guard = crate::context::push("request_id", 42);
+            let _thread = crate::context::push("thread.id", "spoofed");
+            warning!(logger.channel(1), "slow \"request\"");
+            warning!(logger.channel(2), "filtered");
+        }
+        info!(logger, "batched until drop");
+        assert_eq!(logger.sink().batched(), 1);
+        let sink = logger.into_sink();
+        assert_eq!((sink.failed_exports, sink.dropped()), (0, 0));
+        drop(sink);
+        let rejected = bodies.recv().unwrap();
+        assert_eq!(bodies.recv().unwrap(), rejected);
+        assert!(rejected.starts_with(
+            "{\"resourceLogs\":[{\"resource\":{\"attributes\":[\
+             {\"key\":\"service.name\",\"value\":{\"stringValue\":\"app\"}},\
+             {\"key\":\"host.name\",\"value\":{\"stringValue\":\"host\"}}]},\
+             \"scopeLogs\":[{\"scope\":{\"name\":\"logidize\",\"version\":\"",
+        ));
+        let records: Vec<_> = rejected.split("{\"timeUnixNano\":").skip(1).collect();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].contains("\"severityNumber\":9,\"severityText\":\"INFO\",\"body\":{\"stringValue\":\"started\"}"));
+        assert!(records[0].contains("{\"key\":\"logidize.channel\",\"value\":{\"stringValue\":\"main\"}}"));
+        assert!(records[0].contains(&format!("{{\"key\":\"code.filepath\",\"value\":{{\"stringValue\":\"{}\"}}}}", file!())));
+        assert!(records[0].ends_with(&format!(
+            "],\"traceId\":\"{}\",\"spanId\":\"{}\"}},",
+            "01".repeat(16),
+            "ab".repeat(8),
+        )));
+        assert!(records[1].contains("\"severityNumber\":13,\"severityText\":\"WARNING\",\"body\":{\"stringValue\":\"slow \\\"request\\\"\"}"));
+        assert!(records[1].contains("{\"key\":\"logidize.channel\",\"value\":{\"stringValue\":\"net\"}}"));
+        assert!(records[1].contains(
+            "}},{\"key\":\"request_id\",\"value\":{\"stringValue\":\"42\"}},{\"key\":\"context.thread.id\",\"value\":{\"stringValue\":\"spoofed\"}}],",
+        ));
+        assert!(!records[0].contains("request_id"));
+        let last = bodies.recv().unwrap();
+        assert_eq!(last.matches("{\"timeUnixNano\":").count(), 1);
+        assert!(last.contains("batched until drop"));
+    }
+
+    #[test]
+    fn test_trace_context() {
+        fn encode(sink: &OtlpSink) -> String {
+            let context = crate::context::current();
+            sink.encode(&LogObject { context: context.as_context(), ..LogObject::new(0, Level::INFO, format_args!("traced")) }, "main")
+        }
+        let trace_id = "0123456789ABCDEF0123456789abcdef";
+        assert_eq!(TraceContext::from_hex(&"+1".repeat(16), "00000000000000ff"), None);
+        let mut sink = OtlpSink::new("http://localhost:4318/v1/logs", InvisibleChannelFilterMap);
+        let _trace = crate::context::push("trace_id", trace_id);
+        let invalid = crate::context::push("span_id", "not a span");
+        let record = encode(&sink);
+        assert!(!record.contains("traceId"));
+        assert!(record.contains(&format!("{{\"key\":\"trace_id\",\"value\":{{\"stringValue\":\"{trace_id}\"}}}}")));
+        assert!(record.contains("{\"key\":\"span_id\",\"value\":{\"stringValue\":\"not a span\"}}"));
+        drop(invalid);
+        let _span = crate::context::push("span_id", "00000000000000ff");
+        let record = encode(&sink);
+        assert!(!record.contains("_id\""));
+        assert!(record.ends_with(&format!("],\"traceId\":\"{}\",\"spanId\":\"00000000000000ff\"}}", trace_id.to_lowercase())));
+        sink.trace_context = || Some(TraceContext { span_id: [1; 8], trace_id: [2; 16] });
+        assert!(encode(&sink).ends_with(&format!("],\"traceId\":\"{}\",\"spanId\":\"{}\"}}", "02".repeat(16), "01".repeat(8))));
+    }
+
+    #[test]
+    fn test_retry() {
+        let (endpoint, bodies) = collector(&[503, 200, 200]);
+        let mut sink = OtlpSink::new(endpoint, InvisibleChannelFilterMap);
+        sink.max_batch_size = 1;
+        sink.retry_backoff = Duration::from_millis(50);
+        let start = Instant::now();
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("retried")));
+        assert!(sink.is_retrying());
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("batched")));
+        assert!(start.elapsed() < sink.retry_backoff);
+        assert_eq!(sink.batched(), 1);
+        drop(sink);
+        let rejected = bodies.recv().unwrap();
+        assert!(rejected.contains("retried"));
+        assert_eq!(bodies.recv().unwrap(), rejected);
+        assert!(bodies.recv().unwrap().contains("batched"));
+    }
+
+    #[test]
+    fn test_failure() {
+        let (endpoint, bodies) = collector(&[400]);
+        let mut sink = OtlpSink::new(endpoint, InvisibleChannelFilterMap);
+        let capture = CaptureSink::new();
+        sink.error_policy = ErrorPolicy::fallback(capture.clone());
+        sink.consume(LogObject::new(0, Level::ERROR, format_args!("rejected")));
+        sink.flush();
+        assert!(bodies.recv().unwrap().contains("rejected"));
+        assert_eq!((sink.failed_exports, sink.dropped(), sink.batched()), (1, 1, 0));
+        let records = capture.take();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].severity, records[0].message.as_str()), (Level::ERROR, "rejected"));
+    }
+}
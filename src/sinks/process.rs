@@ -0,0 +1,17 @@
+//! Information about the running process used by sinks talking to log collectors.
+
+/// Returns the name of the host or [None] if it can't be determined.
+pub(crate) fn hostname() -> Option<String> {
+    let name = std::fs::read_to_string("/proc/sys/kernel/hostname").ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+/// Returns the name of the running executable or [None] if it can't be determined.
+pub(crate) fn process_name() -> Option<String> {
+    let exe = std::env::current_exe().ok()
+        .or_else(|| std::env::args_os().next().map(Into::into))?;
+    exe.file_stem()?.to_str().map(str::to_owned)
+}
@@ -0,0 +1,244 @@
+//! [RouterSink] for routing [LogObject]s to different [Sink]s.
+
+use std::{collections::BTreeSet, fmt::Debug};
+
+use crate::{
+    loggers::{Level, LogObject},
+    sinks::{Sink, SinkError, forward},
+};
+
+/// Selects the channels a [Route] applies to.
+#[derive(Clone, Debug, Default)]
+pub enum ChannelSelector {
+    /// Selects all channels.
+    #[default]
+    All,
+    /// Selects the channels with the given IDs.
+    Ids(BTreeSet<usize>),
+    /// Selects the channels whose ID the function returns `true` for.
+    Predicate(fn(usize) -> bool),
+}
+
+impl ChannelSelector {
+    /// Returns whether the channel with the given ID is selected.
+    #[must_use]
+    pub fn matches(&self, channel_id: usize) -> bool {
+        match self {
+            ChannelSelector::All => true,
+            ChannelSelector::Ids(ids) => ids.contains(&channel_id),
+            ChannelSelector::Predicate(f) => f(channel_id),
+        }
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for ChannelSelector {
+    fn from(ids: [usize; N]) -> Self {
+        ChannelSelector::Ids(ids.into())
+    }
+}
+
+/// A rule of [RouterSink] passing matching [LogObject]s to a [Sink].
+pub struct Route {
+    /// The channels this route applies to.
+    pub channels: ChannelSelector,
+    /// The route's maximum severity level. [LogObject]s of higher severity don't match.
+    pub max_severity: Level,
+    /// The route's minimum severity level. [LogObject]s of lower severity don't match.
+    pub min_severity: Level,
+    /// The [Sink] matching [LogObject]s are passed to.
+    pub sink: Box<dyn Sink + Send>,
+}
+
+impl Route {
+    /// Constructs a new [Route] to `sink` matching all [LogObject]s.
+    ///
+    /// ```
+    /// # use logidize::{loggers::Level, sinks::{Route, WriteSink}};
+    /// let errors = Route {
+    ///     channels: [1, 2].into(),
+    ///     min_severity: Level::ERROR,
+    ///     ..Route::new(WriteSink::<Vec<u8>>::default())
+    /// };
+    /// ```
+    #[must_use]
+    pub fn new(sink: impl Sink + Send + 'static) -> Self {
+        Self {
+            channels: ChannelSelector::All,
+            max_severity: Level::CRITICAL,
+            min_severity: Level::DEBUG,
+            sink: Box::new(sink),
+        }
+    }
+
+    /// Returns whether the [LogObject] is passed to this route's [Sink].
+    #[must_use]
+    pub fn matches(&self, log_object: &LogObject) -> bool {
+        (self.min_severity..=self.max_severity).contains(&log_object.severity)
+            && self.channels.matches(log_object.channel_id)
+    }
+}
+
+impl Debug for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Route")
+            .field("channels", &self.channels)
+            .field("max_severity", &self.max_severity)
+            .field("min_severity", &self.min_severity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Determines which matching [Route]s of a [RouterSink] are used.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum RouteMode {
+    /// Only the first matching [Route] is used.
+    #[default]
+    FirstMatch,
+    /// All matching [Route]s are used.
+    AllMatches,
+}
+
+/// A [Sink] that passes [LogObject]s to the [Sink]s of matching [Route]s.
+///
+/// [LogObject]s that don't match any [Route] are passed to [RouterSink::default_route] (if any).
+#[derive(Default)]
+pub struct RouterSink {
+    /// The [Sink] used for [LogObject]s not matching any [Route].
+    pub default_route: Option<Box<dyn Sink + Send>>,
+    /// How matching [Route]s are used.
+    pub mode: RouteMode,
+    /// The [Route]s in order of precedence.
+    pub routes: Vec<Route>,
+}
+
+impl RouterSink {
+    /// Constructs a new [RouterSink] without any routes.
+    #[must_use]
+    pub const fn new(mode: RouteMode) -> Self {
+        Self { default_route: None, mode, routes: Vec::new() }
+    }
+}
+
+impl Debug for RouterSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouterSink")
+            .field("default_route", &self.default_route.is_some())
+            .field("mode", &self.mode)
+            .field("routes", &self.routes)
+            .finish()
+    }
+}
+
+impl RouterSink {
+    /// Passes `log_object` to the matching [Route]s (see [forward()]).
+    fn route(&mut self, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+        let mut matched = false;
+        let mut result = Ok(());
+        for route in self.routes.iter_mut().filter(|route| route.matches(&log_object)) {
+            matched = true;
+            result = result.and(forward(&mut *route.sink, log_object, fallible));
+            if self.mode == RouteMode::FirstMatch {
+                break;
+            }
+        }
+        match (matched, &mut self.default_route) {
+            (false, Some(sink)) => forward(&mut **sink, log_object, fallible),
+            _ => result,
+        }
+    }
+}
+
+impl Sink for RouterSink {
+    fn consume(&mut self, log_object: LogObject) {
+        let _ = self.route(log_object, false);
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.route(log_object, true)
+    }
+
+    fn flush(&mut self) {
+        for route in &mut self.routes {
+            route.sink.flush();
+        }
+        if let Some(sink) = &mut self.default_route {
+            sink.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        filter_maps::InvisibleChannelFilterMap,
+        log,
+        loggers::{Logger, multi_threaded::SimpleLogger},
+        sinks::{CaptureSink, ErrorPolicy, WriteSink},
+    };
+
+    type Records = Arc<Mutex<Vec<(usize, Level)>>>;
+
+    fn recorder() -> (Records, impl Sink + Send + 'static) {
+        let records = Records::default();
+        let sink_records = Arc::clone(&records);
+        (records, move |log_object: LogObject| {
+            sink_records.lock().unwrap().push((log_object.channel_id, log_object.severity));
+        })
+    }
+
+    fn log_all(logger: &impl Logger, channel_id: usize) {
+        for severity in [Level::DEBUG, Level::INFO, Level::WARNING, Level::ERROR, Level::CRITICAL] {
+            log!(logger, severity, "channel {channel_id}");
+        }
+    }
+
+    #[test]
+    fn test_first_match() {
+        let (errors, errors_sink) = recorder();
+        let (network, network_sink) = recorder();
+        let (default, default_sink) = recorder();
+        let mut router = RouterSink::new(RouteMode::FirstMatch);
+        router.routes.push(Route { min_severity: Level::ERROR, ..Route::new(errors_sink) });
+        router.routes.push(Route { channels: [1].into(), max_severity: Level::WARNING, ..Route::new(network_sink) });
+        router.default_route = Some(Box::new(default_sink));
+        let logger = SimpleLogger::new(router);
+        log_all(&logger, 0);
+        log_all(&logger.channel(1), 1);
+        assert_eq!(*errors.lock().unwrap(), [(0, Level::ERROR), (0, Level::CRITICAL), (1, Level::ERROR), (1, Level::CRITICAL)]);
+        assert_eq!(*network.lock().unwrap(), [(1, Level::DEBUG), (1, Level::INFO), (1, Level::WARNING)]);
+        assert_eq!(*default.lock().unwrap(), [(0, Level::DEBUG), (0, Level::INFO), (0, Level::WARNING)]);
+    }
+
+    #[test]
+    fn test_all_matches() {
+        let (everything, everything_sink) = recorder();
+        let (odd, odd_sink) = recorder();
+        let (default, default_sink) = recorder();
+        let mut router = RouterSink::new(RouteMode::AllMatches);
+        router.routes.push(Route { min_severity: Level::CRITICAL, ..Route::new(everything_sink) });
+        router.routes.push(Route { channels: ChannelSelector::Predicate(|id| id % 2 == 1), min_severity: Level::ERROR, ..Route::new(odd_sink) });
+        router.default_route = Some(Box::new(default_sink));
+        let logger = SimpleLogger::new(router);
+        log_all(&logger.channel(1), 1);
+        log_all(&logger.channel(2), 2);
+        assert_eq!(*everything.lock().unwrap(), [(1, Level::CRITICAL), (2, Level::CRITICAL)]);
+        assert_eq!(*odd.lock().unwrap(), [(1, Level::ERROR), (1, Level::CRITICAL)]);
+        assert_eq!(default.lock().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let fallback = CaptureSink::new();
+        let mut sink = WriteSink::new(std::io::Cursor::new([0; 0]), InvisibleChannelFilterMap);
+        sink.error_policy = ErrorPolicy::fallback(fallback.clone());
+        let mut router = RouterSink::new(RouteMode::FirstMatch);
+        router.routes.push(Route::new(sink));
+        let logger = SimpleLogger::new(router);
+        log!(logger, Level::INFO, "routed");
+        assert!(logger.sink().unwrap().try_consume(LogObject::new(0, Level::INFO, format_args!("returned"))).is_err());
+        assert_eq!(fallback.take().iter().map(|record| record.message.as_str()).collect::<Vec<_>>(), ["routed"]);
+    }
+}
@@ -0,0 +1,303 @@
+//! [SamplingSink] for passing on only a sample of [LogObject]s.
+
+use std::{
+    collections::btree_map::BTreeMap,
+    hash::{BuildHasher, RandomState},
+};
+
+use crate::{
+    loggers::{Level, LogObject},
+    sinks::{Sink, SinkError},
+};
+
+/// The increment of the SplitMix64 state.
+const SPLITMIX64_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Returns the SplitMix64 output following `state` (i.e. the bits of the advanced state mixed by the finalizer).
+pub(crate) const fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(SPLITMIX64_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A small, seedable pseudorandom number generator (SplitMix64) used by [SamplingSink].
+///
+/// ```
+/// # use logidize::sinks::SamplingRng;
+/// let mut rng1 = SamplingRng::new(42);
+/// let mut rng2 = SamplingRng::new(42);
+/// assert_eq!(rng1.next_u64(), rng2.next_u64());
+/// assert!((0.0..1.0).contains(&rng1.next_f64()));
+/// ```
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct SamplingRng(u64);
+
+impl SamplingRng {
+    /// Constructs a new [SamplingRng] from a seed.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Constructs a new [SamplingRng] from a random seed.
+    #[must_use]
+    pub fn from_random_seed() -> Self {
+        Self(RandomState::new().hash_one(0u64))
+    }
+
+    /// Returns the next pseudorandom [u64].
+    pub fn next_u64(&mut self) -> u64 {
+        let z = splitmix64(self.0);
+        self.0 = self.0.wrapping_add(SPLITMIX64_GAMMA);
+        z
+    }
+
+    /// Returns the next pseudorandom [f64] in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// How a [SamplingSink] samples [LogObject]s of a channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sampling {
+    /// Every [LogObject] is passed on.
+    #[default]
+    All,
+    /// Every [LogObject] is passed on with the given probability (e.g. `0.01` for 1%).
+    Rate(f64),
+    /// Every n-th [LogObject] is passed on, starting with the first.
+    EveryNth(u64),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelCounts {
+    seen: u64,
+    sampled_out: u64,
+}
+
+/// The per-channel counts shared by [SamplingSink] and [SamplingFilterMap](crate::filter_maps::SamplingFilterMap).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SamplingCounts {
+    channels: BTreeMap<usize, ChannelCounts>,
+    sampled_out: u64,
+}
+
+impl SamplingCounts {
+    pub(crate) const fn new() -> Self {
+        Self { channels: BTreeMap::new(), sampled_out: 0 }
+    }
+
+    pub(crate) const fn sampled_out(&self) -> u64 {
+        self.sampled_out
+    }
+
+    pub(crate) fn channel_sampled_out(&self, channel_id: usize) -> u64 {
+        self.channels.get(&channel_id).map_or(0, |counts| counts.sampled_out)
+    }
+
+    /// Returns whether a [LogObject] of the channel is passed on according to `sampling`.
+    pub(crate) fn sample(&mut self, channel_id: usize, sampling: Sampling, rng: &mut SamplingRng) -> bool {
+        let counts = self.channels.entry(channel_id).or_default();
+        let sampled = match sampling {
+            Sampling::All => true,
+            Sampling::Rate(rate) => rng.next_f64() < rate,
+            Sampling::EveryNth(n) => counts.seen.is_multiple_of(n.max(1)),
+        };
+        counts.seen += 1;
+        if !sampled {
+            counts.sampled_out += 1;
+            self.sampled_out += 1;
+        }
+        sampled
+    }
+}
+
+/// A [Sink] that passes only a sample of [LogObject]s to another [Sink].
+///
+/// Each channel is sampled according to its entry in [SamplingSink::channel_sampling] or [SamplingSink::default_sampling].
+/// [LogObject]s of higher severity than [SamplingSink::max_severity] are always passed on.
+#[derive(Clone, Debug)]
+pub struct SamplingSink<S: Sink> {
+    /// The [Sampling]s of channels that don't use [SamplingSink::default_sampling].
+    pub channel_sampling: BTreeMap<usize, Sampling>,
+    /// The [Sampling] of channels without an entry in [SamplingSink::channel_sampling].
+    pub default_sampling: Sampling,
+    /// The sink's maximum sampled severity level. [LogObject]s of higher severity are always passed on.
+    pub max_severity: Level,
+    /// The [SamplingRng] used for [Sampling::Rate].
+    pub rng: SamplingRng,
+    /// The underlying [Sink].
+    pub sink: S,
+    counts: SamplingCounts,
+}
+
+impl<S: Sink> SamplingSink<S> {
+    /// Constructs a new [SamplingSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub fn new(sink: S, default_sampling: Sampling) -> Self {
+        Self {
+            channel_sampling: BTreeMap::new(),
+            default_sampling,
+            max_severity: Level::CRITICAL,
+            rng: SamplingRng::from_random_seed(),
+            sink,
+            counts: SamplingCounts::new(),
+        }
+    }
+
+    /// Returns the total number of [LogObject]s sampled out by this sink.
+    #[must_use]
+    pub const fn sampled_out(&self) -> u64 {
+        self.counts.sampled_out()
+    }
+
+    /// Returns the number of [LogObject]s of the channel sampled out by this sink.
+    #[must_use]
+    pub fn channel_sampled_out(&self, channel_id: usize) -> u64 {
+        self.counts.channel_sampled_out(channel_id)
+    }
+
+    fn sample(&mut self, log_object: &LogObject) -> bool {
+        if log_object.severity > self.max_severity {
+            return true;
+        }
+        let sampling = *self.channel_sampling.get(&log_object.channel_id).unwrap_or(&self.default_sampling);
+        self.counts.sample(log_object.channel_id, sampling, &mut self.rng)
+    }
+}
+
+impl<S: Sink> Sink for SamplingSink<S> {
+    fn consume(&mut self, log_object: LogObject) {
+        if self.sample(&log_object) {
+            self.sink.consume(log_object);
+        }
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        match self.sample(&log_object) {
+            true => self.sink.try_consume(log_object),
+            false => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) {
+        self.sink.flush();
+    }
+}
+
+/// Invokes [log!](crate::log!) only for every n-th invocation of this call site (starting with the first).
+///
+/// Defaults to using [default_logger!](crate::default_logger!).
+///
+/// ```
+/// # use logidize::{*, loggers::{Level, LogObject, single_threaded::SimpleLogger}};
+/// let mut count = 0;
+/// let logger = SimpleLogger::new(|_: LogObject| count += 1);
+/// for i in 0..100 {
+///     log_every_n!(logger, 10, Level::DEBUG, "iteration {i}");
+/// }
+/// # drop(logger);
+/// assert_eq!(count, 10);
+/// ```
+#[macro_export]
+macro_rules! log_every_n {
+    ($n:expr, $lvl:expr, $fmt:literal $(, $($args:tt)*)?) => {
+        $crate::log_every_n!(default_logger!(), $n, $lvl, $fmt $(, $($args)*)?)
+    };
+
+    ($logger:expr, $n:expr, $lvl:expr, $($args:tt)+) => {{
+        static COUNTER: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+        let n: u64 = $n;
+        if COUNTER.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) % n.max(1) == 0 {
+            $crate::log!($logger, $lvl, $($args)+)
+        }
+    }};
+}
+
+/// Invokes [log!](crate::log!) only for the first invocation of this call site.
+///
+/// Defaults to using [default_logger!](crate::default_logger!).
+///
+/// ```
+/// # use logidize::{*, loggers::{Level, LogObject, single_threaded::SimpleLogger}};
+/// let mut count = 0;
+/// let logger = SimpleLogger::new(|_: LogObject| count += 1);
+/// for _ in 0..100 {
+///     log_once!(logger, Level::WARNING, "deprecated");
+/// }
+/// # drop(logger);
+/// assert_eq!(count, 1);
+/// ```
+#[macro_export]
+macro_rules! log_once {
+    ($lvl:expr, $fmt:literal $(, $($args:tt)*)?) => {
+        $crate::log_once!(default_logger!(), $lvl, $fmt $(, $($args)*)?)
+    };
+
+    ($logger:expr, $lvl:expr, $($args:tt)+) => {{
+        static LOGGED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+        if !LOGGED.swap(true, ::std::sync::atomic::Ordering::Relaxed) {
+            $crate::log!($logger, $lvl, $($args)+)
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        debug, error,
+        loggers::{Logger, single_threaded::SimpleLogger},
+    };
+
+    #[test]
+    fn test_every_nth() {
+        let mut messages = Vec::new();
+        let mut sink = SamplingSink::new(|log_object: LogObject| messages.push(log_object.message.to_string()), Sampling::EveryNth(3));
+        sink.channel_sampling.insert(1, Sampling::All);
+        sink.max_severity = Level::DEBUG;
+        let logger = SimpleLogger::new(sink);
+        for i in 0..10 {
+            debug!(logger, "{i}");
+            debug!(logger.channel(1), "channel 1");
+            error!(logger, "error {i}");
+        }
+        assert_eq!(logger.sink().sampled_out(), 6);
+        assert_eq!(logger.sink().channel_sampled_out(0), 6);
+        assert_eq!(logger.sink().channel_sampled_out(1), 0);
+        drop(logger);
+        let debugs: Vec<_> = messages.iter().filter(|message| message.len() == 1).collect();
+        assert_eq!(debugs, ["0", "3", "6", "9"]);
+        assert_eq!(messages.iter().filter(|message| *message == "channel 1").count(), 10);
+        assert_eq!(messages.iter().filter(|message| message.starts_with("error")).count(), 10);
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut count = 0;
+        let mut sink = SamplingSink::new(|_: LogObject| count += 1, Sampling::Rate(0.01));
+        sink.rng = SamplingRng::new(42);
+        let logger = SimpleLogger::new(sink);
+        for _ in 0..100_000 {
+            debug!(logger, "message");
+        }
+        let sampled_out = logger.sink().sampled_out();
+        drop(logger);
+        assert_eq!(count + sampled_out, 100_000);
+        assert!((900..1100).contains(&count), "{count}");
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut messages = Vec::new();
+        let logger = SimpleLogger::new(|log_object: LogObject| messages.push(log_object.message.to_string()));
+        for i in 0..10 {
+            crate::log_every_n!(logger, 4, Level::INFO, "every 4th {i}");
+            crate::log_once!(logger.channel(1), Level::INFO, "once {i}");
+        }
+        let _ = logger.into_sink();
+        assert_eq!(messages, ["every 4th 0", "once 0", "every 4th 4", "every 4th 8"]);
+    }
+}
@@ -0,0 +1,449 @@
+//! [SyslogSink] for sending [LogObject]s to a syslog daemon.
+
+use std::{
+    fmt::{Display, Write as _},
+    io::Write,
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    time::UNIX_EPOCH,
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixDatagram, path::Path};
+
+use crate::{
+    context::Context,
+    filter_maps::{ChannelFilterMap, InvisibleChannelFilterMap},
+    loggers::{Level, LogObject},
+    sinks::{ErrorPolicy, Sink, SinkError, network, process, timestamp::{Rfc3339, civil_from_days}},
+};
+
+/// A syslog facility (see RFC 5424, section 6.2.1).
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(missing_docs)]
+pub enum Facility {
+    Kern = 0,
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// The message format of a [SyslogSink].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// [RFC 5424](https://www.rfc-editor.org/rfc/rfc5424) with the channel as `MSGID`
+    /// and the [LogObject::context] as `STRUCTURED-DATA` (see [SyslogSink::structured_data_id]).
+    #[default]
+    Rfc5424,
+    /// The legacy BSD format of [RFC 3164](https://www.rfc-editor.org/rfc/rfc3164) with the channel
+    /// and the [LogObject::context] prefixed to the message.
+    ///
+    /// Timestamps are in UTC.
+    Rfc3164,
+}
+
+/// How a [SyslogSink] sends messages to the daemon.
+#[derive(Debug)]
+pub enum SyslogTransport {
+    /// One datagram per message over a Unix socket (e.g. `/dev/log`).
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+    /// One datagram per message over UDP.
+    Udp(UdpSocket),
+    /// Octet-counted messages (see [RFC 6587](https://www.rfc-editor.org/rfc/rfc6587#section-3.4.1)) over TCP.
+    Tcp(TcpStream),
+}
+
+impl SyslogTransport {
+    /// Connects to the local daemon via `/dev/log`.
+    #[cfg(unix)]
+    pub fn local() -> std::io::Result<Self> {
+        Self::unix("/dev/log")
+    }
+
+    /// Connects to a daemon listening on a Unix datagram socket.
+    #[cfg(unix)]
+    pub fn unix(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(SyslogTransport::Unix(socket))
+    }
+
+    /// Connects to a daemon listening on UDP.
+    pub fn udp(address: impl ToSocketAddrs) -> std::io::Result<Self> {
+        network::connect_udp(address).map(SyslogTransport::Udp)
+    }
+
+    /// Connects to a daemon listening on TCP.
+    pub fn tcp(address: impl ToSocketAddrs) -> std::io::Result<Self> {
+        TcpStream::connect(address).map(SyslogTransport::Tcp)
+    }
+
+    fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            SyslogTransport::Unix(socket) => socket.send(message).map(drop),
+            SyslogTransport::Udp(socket) => socket.send(message).map(drop),
+            SyslogTransport::Tcp(stream) => {
+                let mut frame = format!("{} ", message.len()).into_bytes();
+                frame.extend_from_slice(message);
+                stream.write_all(&frame)
+            },
+        }
+    }
+}
+
+/// Returns the syslog severity (see RFC 5424, section 6.2.1) of the level.
+#[must_use]
+pub const fn syslog_severity(level: Level) -> u8 {
+    match level {
+        Level::DEBUG    => 7,
+        Level::INFO     => 6,
+        Level::WARNING  => 4,
+        Level::ERROR    => 3,
+        Level::CRITICAL => 2,
+    }
+}
+
+/// A [Sink] that sends [LogObject]s via a [ChannelFilterMap] to a syslog daemon.
+#[derive(Debug)]
+pub struct SyslogSink<M: ChannelFilterMap = InvisibleChannelFilterMap> {
+    /// The `APP-NAME` (or `TAG` for [SyslogFormat::Rfc3164]).
+    pub app_name: String,
+    /// The [ChannelFilterMap] used.
+    pub channel_map: M,
+    /// How failures of [SyslogSink::transport] are handled by [Sink::consume()].
+    pub error_policy: ErrorPolicy,
+    /// The [Facility] of all messages.
+    pub facility: Facility,
+    /// The number of failed sends that weren't ignored according to [SyslogSink::error_policy].
+    pub failed_writes: u64,
+    /// The message format.
+    pub format: SyslogFormat,
+    /// The `HOSTNAME`.
+    pub hostname: String,
+    /// The sink's minimum severity level. [SyslogSink] won't log [LogObject]s of lower severity.
+    pub min_severity: Level,
+    /// Whether the sink is muted. A muted [SyslogSink] won't log anything.
+    pub muted: bool,
+    /// The `PROCID`.
+    pub procid: u32,
+    /// The `SD-ID` of the `STRUCTURED-DATA` element carrying the [LogObject::context].
+    ///
+    /// Should contain your organization's IANA Private Enterprise Number (the default uses the one reserved for documentation).
+    pub structured_data_id: String,
+    /// The [SyslogTransport] used.
+    pub transport: SyslogTransport,
+    reported: bool,
+}
+
+impl<M: ChannelFilterMap> SyslogSink<M> {
+    /// Constructs a new [SyslogSink] with default settings (that shouldn't be relied upon).
+    ///
+    /// [SyslogSink::hostname], [SyslogSink::app_name] and [SyslogSink::procid] are detected from the running process.
+    #[must_use]
+    pub fn new(transport: SyslogTransport, channel_map: M) -> Self {
+        Self {
+            app_name: process::process_name().unwrap_or_else(|| "-".into()),
+            channel_map,
+            error_policy: ErrorPolicy::Count,
+            facility: Facility::User,
+            failed_writes: 0,
+            format: SyslogFormat::Rfc5424,
+            hostname: process::hostname().unwrap_or_else(|| "-".into()),
+            min_severity: Level::DEBUG,
+            muted: false,
+            procid: std::process::id(),
+            structured_data_id: "logidize@32473".into(),
+            transport,
+            reported: false,
+        }
+    }
+
+    /// Formats a [LogObject] in [SyslogSink::format] with the channel displayed as `channel`.
+    fn format_message(&self, log_object: &LogObject, channel: impl Display) -> String {
+        let pri = (self.facility as u8) * 8 + syslog_severity(log_object.severity);
+        let mut message = String::new();
+        let _ = match self.format {
+            SyslogFormat::Rfc5424 => write!(
+                message,
+                "<{pri}>1 {} {} {} {} {} {} {}",
+                Rfc3339(log_object.time),
+                Header(&self.hostname, 255),
+                Header(&self.app_name, 48),
+                self.procid,
+                Header(&channel.to_string(), 32),
+                StructuredData(&self.structured_data_id, log_object.context),
+                log_object.message,
+            ),
+            SyslogFormat::Rfc3164 => write!(
+                message,
+                "<{pri}>{} {} {}[{}]: [{channel}]{} {}",
+                BsdTimestamp(log_object.time),
+                Header(&self.hostname, 255),
+                Header(&self.app_name, 32),
+                self.procid,
+                BsdContext(log_object.context),
+                log_object.message,
+            ),
+        };
+        message
+    }
+
+    /// Formats and sends `log_object` unless it's filtered out.
+    fn write_record(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        if self.muted || log_object.severity < self.min_severity {
+            return Ok(());
+        }
+        let Some(channel_name) = self.channel_map.filter_map(&log_object) else {
+            return Ok(());
+        };
+        let message = self.format_message(&log_object, channel_name);
+        Ok(self.transport.send(message.as_bytes())?)
+    }
+}
+
+impl<M: ChannelFilterMap> Sink for SyslogSink<M> {
+    fn consume(&mut self, log_object: LogObject) {
+        if let Err(error) = self.write_record(log_object) {
+            self.error_policy.handle(&mut self.failed_writes, &mut self.reported, error, Some(log_object));
+        }
+    }
+
+    /// Failures are counted in [SyslogSink::failed_writes] (unless [ErrorPolicy::Ignore]d) but otherwise returned instead of handled.
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        let result = self.write_record(log_object);
+        if result.is_err() {
+            self.error_policy.count(&mut self.failed_writes);
+        }
+        result
+    }
+}
+
+/// Displays a header field as printable ASCII without spaces (truncated to a maximum length) or `-` if empty.
+struct Header<'a>(&'a str, usize);
+
+impl Display for Header<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_char('-');
+        }
+        for c in self.0.chars().take(self.1) {
+            f.write_char(if c.is_ascii_graphic() { c } else { '_' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays an SD-ID and a [Context] as an RFC 5424 `STRUCTURED-DATA` element or `-` if the context is empty.
+struct StructuredData<'a>(&'a str, Context<'a>);
+
+impl Display for StructuredData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.1.is_empty() {
+            return f.write_char('-');
+        }
+        write!(f, "[{}", SdName(self.0))?;
+        for (key, value) in self.1.iter() {
+            write!(f, " {}=\"", SdName(key))?;
+            for c in value.chars() {
+                if matches!(c, '"' | '\\' | ']') {
+                    f.write_char('\\')?;
+                }
+                f.write_char(c)?;
+            }
+            f.write_char('"')?;
+        }
+        f.write_char(']')
+    }
+}
+
+/// Displays an `SD-NAME`: at most 32 printable ASCII characters except `=`, ` `, `]` and `"`.
+struct SdName<'a>(&'a str);
+
+impl Display for SdName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_char('_');
+        }
+        for c in self.0.chars().take(32) {
+            f.write_char(if c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"') { c } else { '_' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a [Context] as `[key=value ...]` or nothing if it is empty.
+struct BsdContext<'a>(Context<'a>);
+
+impl Display for BsdContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => write!(f, "[{}]", self.0),
+        }
+    }
+}
+
+/// Displays a [SystemTime](std::time::SystemTime) as an RFC 3164 timestamp (e.g. `Feb  5 17:32:18`) in UTC.
+struct BsdTimestamp(std::time::SystemTime);
+
+impl Display for BsdTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+        let secs = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+        let (_, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let secs_of_day = secs.rem_euclid(86_400);
+        write!(
+            f,
+            "{} {day:>2} {:02}:{:02}:{:02}",
+            MONTHS[month as usize - 1],
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, net::TcpListener, time::Duration};
+
+    use super::*;
+    use crate::{
+        error, info, warning,
+        filter_maps::StaticChannelFilterMap,
+        loggers::{Logger, single_threaded::SimpleLogger},
+    };
+
+    fn configure<M: ChannelFilterMap>(sink: &mut SyslogSink<M>) {
+        sink.app_name = "app".into();
+        sink.hostname = "host".into();
+        sink.procid = 42;
+    }
+
+    fn strip_timestamp(message: &str, format: SyslogFormat) -> String {
+        let (pri, rest) = message.split_once('>').unwrap();
+        let rest = match format {
+            SyslogFormat::Rfc5424 => rest.split_once(' ').unwrap().1.split_once(' ').unwrap().1,
+            SyslogFormat::Rfc3164 => &rest[16..],
+        };
+        format!("{pri}> {rest}")
+    }
+
+    #[test]
+    fn test_udp_ipv6() {
+        let Ok(daemon) = UdpSocket::bind(("::1", 0)) else {
+            return;
+        };
+        daemon.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut transport = SyslogTransport::udp(daemon.local_addr().unwrap()).unwrap();
+        transport.send(b"ipv6").unwrap();
+        let mut buf = [0; 16];
+        let len = daemon.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ipv6");
+    }
+
+    #[test]
+    fn test_udp() {
+        let daemon = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        daemon.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let transport = SyslogTransport::udp(daemon.local_addr().unwrap()).unwrap();
+        let mut sink = SyslogSink::new(transport, StaticChannelFilterMap(&["main", "net channel"]));
+        configure(&mut sink);
+        sink.facility = Facility::Local0;
+        sink.min_severity = Level::INFO;
+        let logger = SimpleLogger::new(sink);
+        crate::debug!(logger, "filtered");
+        info!(logger, "started");
+        error!(logger.channel(1), "timeout");
+        warning!(logger.channel(2), "filtered");
+        logger.sink().format = SyslogFormat::Rfc3164;
+        warning!(logger, "legacy");
+        {
+            let _guard = crate::context::push("request_id", "a\"b]");
+            info!(logger, "with context");
+            logger.sink().format = SyslogFormat::Rfc5424;
+            info!(logger, "with context");
+        }
+        let mut buf = [0; 1024];
+        let mut receive = || {
+            let len = daemon.recv(&mut buf).unwrap();
+            String::from_utf8(buf[..len].to_vec()).unwrap()
+        };
+        let first = receive();
+        assert_eq!(strip_timestamp(&first, SyslogFormat::Rfc5424), "<134> host app 42 main - started");
+        assert!(first.starts_with("<134>1 ") && first[7..].split_once(' ').unwrap().0.ends_with('Z'));
+        assert_eq!(strip_timestamp(&receive(), SyslogFormat::Rfc5424), "<131> host app 42 net_channel - timeout");
+        let legacy = receive();
+        assert_eq!(strip_timestamp(&legacy, SyslogFormat::Rfc3164), "<132> host app[42]: [main] legacy");
+        assert_eq!(&legacy[8..9], " ");
+        assert_eq!(strip_timestamp(&receive(), SyslogFormat::Rfc3164), "<134> host app[42]: [main][request_id=a\"b]] with context");
+        assert_eq!(
+            strip_timestamp(&receive(), SyslogFormat::Rfc5424),
+            "<134> host app 42 main [logidize@32473 request_id=\"a\\\"b\\]\"] with context",
+        );
+    }
+
+    #[test]
+    fn test_tcp() {
+        let daemon = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut sink = SyslogSink::new(SyslogTransport::tcp(daemon.local_addr().unwrap()).unwrap(), InvisibleChannelFilterMap);
+        configure(&mut sink);
+        let (mut stream, _) = daemon.accept().unwrap();
+        sink.consume(LogObject::new(3, Level::CRITICAL, format_args!("first")));
+        sink.consume(LogObject::new(3, Level::DEBUG, format_args!("second message")));
+        drop(sink);
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        let mut frames = Vec::new();
+        let mut rest = received.as_str();
+        while let Some((len, tail)) = rest.split_once(' ') {
+            let len: usize = len.parse().unwrap();
+            frames.push(strip_timestamp(&tail[..len], SyslogFormat::Rfc5424));
+            rest = &tail[len..];
+        }
+        assert_eq!(frames, ["<10> host app 42 3 - first", "<15> host app 42 3 - second message"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix() {
+        let path = std::env::temp_dir().join(format!("logidize-syslog-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let daemon = UnixDatagram::bind(&path).unwrap();
+        let mut sink = SyslogSink::new(SyslogTransport::unix(&path).unwrap(), InvisibleChannelFilterMap);
+        configure(&mut sink);
+        sink.consume(LogObject::new(0, Level::INFO, format_args!("via unix socket")));
+        let mut buf = [0; 1024];
+        let len = daemon.recv(&mut buf).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(strip_timestamp(message, SyslogFormat::Rfc5424), "<14> host app 42 0 - via unix socket");
+    }
+
+    #[test]
+    fn test_bsd_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(BsdTimestamp(time).to_string(), "Nov 14 22:13:20");
+        assert_eq!(BsdTimestamp(UNIX_EPOCH).to_string(), "Jan  1 00:00:00");
+    }
+}
@@ -0,0 +1,347 @@
+//! [ThrottleSink] for rate-limiting [LogObject]s.
+
+use std::{
+    collections::btree_map::BTreeMap,
+    panic::Location,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    loggers::{Level, LogObject},
+    sinks::{Sink, SinkError, forward, synthesize},
+};
+
+/// Determines which [LogObject]s share a token bucket of a [ThrottleSink].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum ThrottleKey {
+    /// [LogObject]s with the same [LogObject::channel_id] and [LogObject::severity] share a bucket.
+    #[default]
+    ChannelSeverity,
+    /// [LogObject]s with the same [LogObject::channel_id] and [LogObject::location] share a bucket.
+    CallSite,
+}
+
+/// A token bucket limit of a [ThrottleSink].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThrottleLimit {
+    /// The maximum number of [LogObject]s passed on in a burst (i.e. the bucket's capacity).
+    pub burst: u32,
+    /// The number of [LogObject]s passed on per second in the long run (i.e. the bucket's refill rate).
+    pub per_second: f64,
+}
+
+impl ThrottleLimit {
+    /// Constructs a new [ThrottleLimit].
+    ///
+    /// ```
+    /// # use logidize::sinks::ThrottleLimit;
+    /// let l1 = ThrottleLimit::new(10, 1.0);
+    /// let l2 = ThrottleLimit { burst: 10, per_second: 1.0 };
+    /// assert_eq!(l1, l2);
+    /// ```
+    #[must_use]
+    pub const fn new(burst: u32, per_second: f64) -> Self {
+        Self { burst, per_second }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum BucketKey {
+    ChannelSeverity(usize, Level),
+    CallSite(usize, &'static str, u32, u32),
+}
+
+impl BucketKey {
+    const fn channel_id(&self) -> usize {
+        match *self {
+            BucketKey::ChannelSeverity(channel_id, _) | BucketKey::CallSite(channel_id, ..) => channel_id,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    location: &'static Location<'static>,
+    severity: Level,
+    suppressed: u64,
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A [Sink] that passes [LogObject]s to another [Sink] unless they exceed a [ThrottleLimit].
+///
+/// Every group of [LogObject]s determined by [ThrottleSink::key] has its own token bucket,
+/// limited by the channel's entry in [ThrottleSink::channel_limits] or [ThrottleSink::default_limit].
+/// Every [ThrottleSink::summary_interval] a summary of suppressed [LogObject]s is logged on their channel
+/// with their severity but at least [Level::WARNING].
+/// Summaries are only logged when the [ThrottleSink] is used (i.e. by [Sink::consume()] or [Sink::flush()]).
+/// Whenever summaries are due, buckets that have refilled completely and have nothing to summarize are evicted.
+#[derive(Clone, Debug)]
+pub struct ThrottleSink<S: Sink> {
+    /// The [ThrottleLimit]s of channels that don't use [ThrottleSink::default_limit].
+    pub channel_limits: BTreeMap<usize, ThrottleLimit>,
+    /// The [ThrottleLimit] of channels without an entry in [ThrottleSink::channel_limits].
+    pub default_limit: ThrottleLimit,
+    /// How [LogObject]s are grouped into token buckets.
+    pub key: ThrottleKey,
+    /// The underlying [Sink].
+    pub sink: S,
+    /// The minimum interval between summaries of suppressed [LogObject]s.
+    pub summary_interval: Duration,
+    buckets: BTreeMap<BucketKey, Bucket>,
+    last_summary: Option<Instant>,
+    suppressed: u64,
+}
+
+impl<S: Sink> ThrottleSink<S> {
+    /// Constructs a new [ThrottleSink] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(sink: S, default_limit: ThrottleLimit) -> Self {
+        Self {
+            channel_limits: BTreeMap::new(),
+            default_limit,
+            key: ThrottleKey::ChannelSeverity,
+            sink,
+            summary_interval: Duration::from_secs(10),
+            buckets: BTreeMap::new(),
+            last_summary: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns the total number of [LogObject]s suppressed by this sink.
+    #[must_use]
+    pub const fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// Returns the number of token buckets currently kept.
+    #[must_use]
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn limit(&self, channel_id: usize) -> ThrottleLimit {
+        *self.channel_limits.get(&channel_id).unwrap_or(&self.default_limit)
+    }
+
+    fn admit(&mut self, log_object: &LogObject, now: Instant) -> bool {
+        let limit = self.limit(log_object.channel_id);
+        let key = match self.key {
+            ThrottleKey::ChannelSeverity => BucketKey::ChannelSeverity(log_object.channel_id, log_object.severity),
+            ThrottleKey::CallSite => {
+                let location = log_object.location;
+                BucketKey::CallSite(log_object.channel_id, location.file(), location.line(), location.column())
+            },
+        };
+        let bucket = self.buckets.entry(key).or_insert(Bucket {
+            location: log_object.location,
+            severity: log_object.severity,
+            suppressed: 0,
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            bucket.suppressed += 1;
+            self.suppressed += 1;
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Summarizes the suppressed [LogObject]s if [ThrottleSink::summary_interval] has elapsed.
+    fn summarize(&mut self, now: Instant, trigger: Option<&LogObject>, fallible: bool) -> Result<(), SinkError> {
+        let last_summary = *self.last_summary.get_or_insert(now);
+        let elapsed = now.duration_since(last_summary);
+        if elapsed < self.summary_interval {
+            return Ok(());
+        }
+        self.last_summary = Some(now);
+        let mut result = Ok(());
+        for (key, bucket) in &mut self.buckets {
+            if bucket.suppressed == 0 {
+                continue;
+            }
+            let suppressed = std::mem::take(&mut bucket.suppressed);
+            let secs = elapsed.as_secs();
+            let severity = bucket.severity.max(Level::WARNING);
+            let plural = if suppressed == 1 { "" } else { "s" };
+            let summary = match key {
+                BucketKey::ChannelSeverity(channel_id, _) => forward(&mut self.sink, synthesize(
+                    trigger,
+                    *channel_id,
+                    severity,
+                    format_args!("suppressed {suppressed} {} message{plural} in last {secs}s", bucket.severity),
+                ), fallible),
+                BucketKey::CallSite(channel_id, ..) => forward(&mut self.sink, synthesize(
+                    trigger,
+                    *channel_id,
+                    severity,
+                    format_args!("suppressed {suppressed} message{plural} from {} in last {secs}s", bucket.location),
+                ), fallible),
+            };
+            result = result.and(summary);
+        }
+        let mut buckets = std::mem::take(&mut self.buckets);
+        buckets.retain(|key, bucket| {
+            let limit = self.limit(key.channel_id());
+            let tokens = bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * limit.per_second;
+            bucket.suppressed > 0 || tokens < limit.burst as f64
+        });
+        self.buckets = buckets;
+        result
+    }
+
+    /// Passes `log_object` on unless it's suppressed (see [forward()]).
+    fn throttle(&mut self, log_object: LogObject, fallible: bool) -> Result<(), SinkError> {
+        let now = Instant::now();
+        let summary = self.summarize(now, Some(&log_object), fallible);
+        match self.admit(&log_object, now) {
+            true => forward(&mut self.sink, log_object, fallible).and(summary),
+            false => summary,
+        }
+    }
+}
+
+impl<S: Sink> Sink for ThrottleSink<S> {
+    fn consume(&mut self, log_object: LogObject) {
+        let _ = self.throttle(log_object, false);
+    }
+
+    fn try_consume(&mut self, log_object: LogObject) -> Result<(), SinkError> {
+        self.throttle(log_object, true)
+    }
+
+    fn flush(&mut self) {
+        let _ = self.summarize(Instant::now(), None, false);
+        self.sink.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{
+        clock::Clock,
+        error,
+        filter_maps::InvisibleChannelFilterMap,
+        info,
+        loggers::{Logger, Stamping, ThreadInfo, multi_threaded::SimpleLogger, single_threaded},
+        sinks::{CaptureSink, ErrorPolicy, WriteSink},
+    };
+
+    fn collect(messages: &mut Vec<(usize, Level, String)>) -> impl FnMut(LogObject) + Send + '_ {
+        |log_object: LogObject| messages.push((log_object.channel_id, log_object.severity, log_object.message.to_string()))
+    }
+
+    #[test]
+    fn test_channel_severity() {
+        let mut messages = Vec::new();
+        let mut sink = ThrottleSink::new(collect(&mut messages), ThrottleLimit::new(2, 0.0));
+        sink.channel_limits.insert(1, ThrottleLimit::new(3, 0.0));
+        sink.summary_interval = Duration::from_secs(3600);
+        let logger = SimpleLogger::new(sink);
+        for i in 0..5 {
+            info!(logger, "info {i}");
+            error!(logger, "error {i}");
+            info!(logger.channel(1), "info {i}");
+        }
+        let mut sink = logger.into_sink().unwrap();
+        assert_eq!(sink.suppressed(), 3 + 3 + 2);
+        sink.summary_interval = Duration::ZERO;
+        sink.flush();
+        assert_eq!(sink.suppressed(), 8);
+        drop(sink);
+        let messages: Vec<_> = messages.iter().map(|(id, severity, message)| (*id, *severity, message.as_str())).collect();
+        assert_eq!(messages, [
+            (0, Level::INFO, "info 0"),
+            (0, Level::ERROR, "error 0"),
+            (1, Level::INFO, "info 0"),
+            (0, Level::INFO, "info 1"),
+            (0, Level::ERROR, "error 1"),
+            (1, Level::INFO, "info 1"),
+            (1, Level::INFO, "info 2"),
+            (0, Level::WARNING, "suppressed 3 INFO messages in last 0s"),
+            (0, Level::ERROR, "suppressed 3 ERROR messages in last 0s"),
+            (1, Level::WARNING, "suppressed 2 INFO messages in last 0s"),
+        ]);
+    }
+
+    #[test]
+    fn test_call_site() {
+        let mut sink = ThrottleSink::new(|_: LogObject| (), ThrottleLimit::new(1, 0.0));
+        sink.key = ThrottleKey::CallSite;
+        let logger = SimpleLogger::new(sink);
+        std::thread::scope(|scope| {
+            for _ in 0..10 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        info!(logger, "first call site");
+                        info!(logger, "second call site");
+                    }
+                });
+            }
+        });
+        assert_eq!(logger.into_sink().unwrap().suppressed(), 2 * (10 * 100 - 1));
+    }
+
+    #[test]
+    fn test_eviction() {
+        let capture = CaptureSink::new();
+        let mut sink = ThrottleSink::new(capture.clone(), ThrottleLimit::new(1, 100.0));
+        sink.key = ThrottleKey::CallSite;
+        sink.summary_interval = Duration::ZERO;
+        let logger = single_threaded::SimpleLogger::new(sink);
+        for _ in 0..2 {
+            info!(logger, "first call site");
+            info!(logger.channel(1), "second call site");
+        }
+        assert_eq!((logger.sink().buckets(), logger.sink().suppressed()), (2, 2));
+        std::thread::sleep(Duration::from_millis(50));
+        logger.flush();
+        assert_eq!(logger.sink().buckets(), 0);
+        let messages: Vec<_> = capture.take().into_iter().map(|record| record.message).collect();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[2..].iter().all(|message| message.starts_with("suppressed 1 message from ")));
+    }
+
+    #[test]
+    fn test_summary_stamp() {
+        let capture = CaptureSink::new();
+        let mut sink = ThrottleSink::new(capture.clone(), ThrottleLimit::new(1, 0.0));
+        sink.summary_interval = Duration::ZERO;
+        let time = UNIX_EPOCH + Duration::from_secs(42);
+        let thread = ThreadInfo { id: 7, name: None, os_id: None };
+        let logger = single_threaded::SimpleLogger::new(sink).with_stamping(Stamping { clock: Clock::Fixed(time), thread: Some(thread) });
+        for _ in 0..3 {
+            info!(logger, "throttled");
+        }
+        let records = capture.take();
+        assert_eq!(records[1].message, "suppressed 1 INFO message in last 0s");
+        assert_eq!((records[1].time, records[1].thread), (time, thread));
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let fallback = CaptureSink::new();
+        let mut output = WriteSink::new(std::io::Cursor::new([0; 0]), InvisibleChannelFilterMap);
+        output.error_policy = ErrorPolicy::fallback(fallback.clone());
+        let mut sink = ThrottleSink::new(output, ThrottleLimit::new(1, 0.0));
+        sink.summary_interval = Duration::ZERO;
+        let logger = single_threaded::SimpleLogger::new(sink);
+        info!(logger, "first");
+        info!(logger, "second");
+        logger.flush();
+        assert_eq!(logger.sink().sink.failed_writes, 2);
+        assert_eq!(
+            fallback.take().iter().map(|record| record.message.as_str()).collect::<Vec<_>>(),
+            ["first", "suppressed 1 INFO message in last 0s"],
+        );
+    }
+}
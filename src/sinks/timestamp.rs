@@ -0,0 +1,61 @@
+//! Formatting of [SystemTime]s.
+
+use std::{fmt::Display, time::{SystemTime, UNIX_EPOCH}};
+
+/// Displays a [SystemTime] as an RFC 3339 timestamp in UTC with microsecond precision.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct Rfc3339(pub SystemTime);
+
+impl Display for Rfc3339 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (secs, micros) = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (duration.as_secs() as i64, duration.subsec_micros()),
+            Err(e) => {
+                let duration = e.duration();
+                match duration.subsec_micros() {
+                    0 => (-(duration.as_secs() as i64), 0),
+                    micros => (-(duration.as_secs() as i64) - 1, 1_000_000 - micros),
+                }
+            },
+        };
+        let (days, secs_of_day) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{micros:06}Z",
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+        )
+    }
+}
+
+/// Converts days since the UNIX epoch into a `(year, month, day)` of the proleptic Gregorian calendar.
+pub(crate) const fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_rfc3339() {
+        let format = |secs: u64, micros: u32| Rfc3339(UNIX_EPOCH + Duration::new(secs, micros * 1000)).to_string();
+        assert_eq!(format(0, 0), "1970-01-01T00:00:00.000000Z");
+        assert_eq!(format(951_782_400, 1), "2000-02-29T00:00:00.000001Z");
+        assert_eq!(format(1_700_000_000, 123_456), "2023-11-14T22:13:20.123456Z");
+        assert_eq!(Rfc3339(UNIX_EPOCH - Duration::from_micros(1)).to_string(), "1969-12-31T23:59:59.999999Z");
+    }
+}
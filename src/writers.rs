@@ -1,9 +1,113 @@
 //! Sensible [Write]rs.
 
+use std::{
+    fs::File,
+    io::{BufWriter, IsTerminal, LineWriter, Stderr, StderrLock, Stdout},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::loggers::Level;
+
 #[doc(no_inline)]
 pub use std::io::Write;
 
-/// A [Write] that writes to [Stderr](std::io::Stderr).
+/// A [Write] that knows whether it writes to a terminal and where the records written by a logger end.
+///
+/// Used by [ColorMode::Auto](crate::colors::ColorMode::Auto) and [WriteSink](crate::sinks::WriteSink).
+/// Custom [Write]rs can implement this trait without overriding any methods to never be considered a terminal.
+pub trait LogWrite: Write {
+    /// Returns whether the output goes to a terminal.
+    ///
+    /// The default implementation returns `false`.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Marks the end of a record of the given severity (called by [WriteSink](crate::sinks::WriteSink) after writing one).
+    ///
+    /// The default implementation does nothing.
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        let _ = severity;
+        Ok(())
+    }
+}
+
+impl LogWrite for Vec<u8> {}
+impl LogWrite for std::io::Sink {}
+impl LogWrite for std::io::Cursor<Vec<u8>> {}
+impl LogWrite for std::io::Cursor<&mut Vec<u8>> {}
+impl LogWrite for std::io::Cursor<&mut [u8]> {}
+impl<const N: usize> LogWrite for std::io::Cursor<[u8; N]> {}
+
+impl LogWrite for File {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl LogWrite for Stderr {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl LogWrite for Stdout {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl<W: LogWrite + ?Sized> LogWrite for &mut W {
+    fn is_terminal(&self) -> bool {
+        (**self).is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        (**self).end_record(severity)
+    }
+}
+
+impl<W: LogWrite + ?Sized> LogWrite for Box<W> {
+    fn is_terminal(&self) -> bool {
+        (**self).is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        (**self).end_record(severity)
+    }
+}
+
+/// The buffered part of the record is written to the inner [LogWrite] before its end is forwarded.
+impl<W: LogWrite> LogWrite for BufWriter<W> {
+    fn is_terminal(&self) -> bool {
+        self.get_ref().is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        if !self.buffer().is_empty() {
+            self.flush()?;
+        }
+        self.get_mut().end_record(severity)
+    }
+}
+
+/// The buffered part of the record is written to the inner [LogWrite] before its end is forwarded.
+impl<W: LogWrite> LogWrite for LineWriter<W> {
+    fn is_terminal(&self) -> bool {
+        self.get_ref().is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        self.flush()?;
+        self.get_mut().end_record(severity)
+    }
+}
+
+/// A [Write] that writes to [Stderr].
+///
+/// Every write is passed on separately, so records written from multiple threads may interleave.
+/// [AtomicStderrWriter] writes whole records instead.
 #[derive(Clone, Copy, Debug, Default, Hash)]
 pub struct StderrWriter;
 
@@ -12,12 +116,25 @@ impl Write for StderrWriter {
         std::io::stderr().write(buf)
     }
 
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        std::io::stderr().write_all(buf)
+    }
+
     fn flush(&mut self) -> std::io::Result<()> {
         std::io::stderr().flush()
     }
 }
 
-/// A [Write] that writes to [Stdout](std::io::Stdout).
+impl LogWrite for StderrWriter {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(&std::io::stderr())
+    }
+}
+
+/// A [Write] that writes to [Stdout].
+///
+/// Every write is passed on separately, so records written from multiple threads may interleave.
+/// [AtomicStdoutWriter] writes whole records instead.
 #[derive(Clone, Copy, Debug, Default, Hash)]
 pub struct StdoutWriter;
 
@@ -26,33 +143,730 @@ impl Write for StdoutWriter {
         std::io::stdout().write(buf)
     }
 
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        std::io::stdout().write_all(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+impl LogWrite for StdoutWriter {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(&std::io::stdout())
+    }
+}
+
+/// A [Write] that writes records to [Stderr] atomically.
+///
+/// Writes are collected until a record ends (see [LogWrite::end_record()]) or the writer is flushed or dropped
+/// and then written with a single call to [Write::write_all()] under a single [StderrLock],
+/// so records don't interleave with other output.
+#[derive(Clone, Debug, Default, Hash)]
+pub struct AtomicStderrWriter {
+    buffer: Vec<u8>,
+}
+
+impl AtomicStderrWriter {
+    /// Constructs a new [AtomicStderrWriter].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    fn write_buffer(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let result = std::io::stderr().lock().write_all(&self.buffer);
+        self.buffer.clear();
+        result
+    }
+}
+
+impl Write for AtomicStderrWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_buffer()?;
+        std::io::stderr().flush()
+    }
+}
+
+impl LogWrite for AtomicStderrWriter {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(&std::io::stderr())
+    }
+
+    fn end_record(&mut self, _: Level) -> std::io::Result<()> {
+        self.write_buffer()
+    }
+}
+
+impl Drop for AtomicStderrWriter {
+    fn drop(&mut self) {
+        let _ = self.write_buffer();
+    }
+}
+
+/// A [Write] that writes to [Stderr] while holding its lock from the first write of a record until the record ends.
+///
+/// Unlike [AtomicStderrWriter], it doesn't allocate, but a record may take multiple writes,
+/// so it only prevents interleaving with output from the same process.
+/// The lock is also released by [Write::flush()], on a failed write and on drop.
+/// As the lock is bound to its thread, [LockedStderrWriter] isn't [Send].
+#[derive(Debug, Default)]
+pub struct LockedStderrWriter {
+    lock: Option<StderrLock<'static>>,
+}
+
+impl LockedStderrWriter {
+    /// Constructs a new [LockedStderrWriter] that doesn't hold the lock yet.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { lock: None }
+    }
+}
+
+impl Write for LockedStderrWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let result = self.lock.get_or_insert_with(|| std::io::stderr().lock()).write(buf);
+        if result.is_err() {
+            self.lock = None;
+        }
+        result
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let result = self.lock.as_mut().map_or(Ok(()), Write::flush);
+        self.lock = None;
+        result
+    }
+}
+
+impl LogWrite for LockedStderrWriter {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(&std::io::stderr())
+    }
+
+    fn end_record(&mut self, _: Level) -> std::io::Result<()> {
+        self.lock = None;
+        Ok(())
+    }
+}
+
+/// A [Write] that writes records to [Stdout] atomically.
+///
+/// Writes are collected until a record ends (see [LogWrite::end_record()]) or the writer is flushed or dropped
+/// and then written with a single call to [Write::write_all()] under a single [StdoutLock](std::io::StdoutLock),
+/// so records don't interleave with other output.
+#[derive(Clone, Debug, Default, Hash)]
+pub struct AtomicStdoutWriter {
+    buffer: Vec<u8>,
+}
+
+impl AtomicStdoutWriter {
+    /// Constructs a new [AtomicStdoutWriter].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    fn write_buffer(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let result = std::io::stdout().lock().write_all(&self.buffer);
+        self.buffer.clear();
+        result
+    }
+}
+
+impl Write for AtomicStdoutWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
     fn flush(&mut self) -> std::io::Result<()> {
+        self.write_buffer()?;
         std::io::stdout().flush()
     }
 }
 
-#[doc(hidden)]
+impl LogWrite for AtomicStdoutWriter {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(&std::io::stdout())
+    }
+
+    fn end_record(&mut self, _: Level) -> std::io::Result<()> {
+        self.write_buffer()
+    }
+}
+
+impl Drop for AtomicStdoutWriter {
+    fn drop(&mut self) {
+        let _ = self.write_buffer();
+    }
+}
+
+/// A [Write] that accumulates whole records and writes them to another [Write] in batches.
+///
+/// Buffered records are written with a single call to [Write::write_all()] when a record ends
+/// (see [LogWrite::end_record()]) and the buffer reaches [BufferedWriter::capacity],
+/// [BufferedWriter::flush_interval] has elapsed since the last write or the record's severity is at least [BufferedWriter::flush_severity].
+/// [Write::flush()] writes all complete records. Dropping the [BufferedWriter] writes everything.
+/// A record is never split across two writes.
+/// Record ends are only signaled by a [WriteSink](crate::sinks::WriteSink) constructed with [WriteSink::new()](crate::sinks::WriteSink::new()).
+///
+/// ```
+/// # use logidize::{*, filter_maps::InvisibleChannelFilterMap, loggers::single_threaded::SimpleLogger, sinks::WriteSink, writers::BufferedWriter};
+/// let logger = SimpleLogger::new(WriteSink::new(BufferedWriter::new(Vec::new()), InvisibleChannelFilterMap));
+/// info!(logger, "buffered");
+/// assert!(logger.sink().output.output.is_empty());
+/// error!(logger, "flushed");
+/// assert_eq!(String::from_utf8_lossy(&logger.sink().output.output).lines().count(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BufferedWriter<W: Write> {
+    /// The number of buffered bytes at which the buffer is written when a record ends.
+    pub capacity: usize,
+    /// The time after the last write at which the buffer is written when a record ends.
+    pub flush_interval: Duration,
+    /// The minimum severity of a record that causes the buffer to be written (and flushed) when it ends.
+    pub flush_severity: Level,
+    /// The underlying [Write].
+    pub output: W,
+    buffer: Vec<u8>,
+    complete: usize,
+    last_write: Option<Instant>,
+}
+
+impl<W: Write> BufferedWriter<W> {
+    /// Constructs a new [BufferedWriter] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(output: W) -> Self {
+        Self {
+            capacity: 8 << 10,
+            flush_interval: Duration::from_secs(1),
+            flush_severity: Level::ERROR,
+            output,
+            buffer: Vec::new(),
+            complete: 0,
+            last_write: None,
+        }
+    }
+
+    /// Returns the number of buffered bytes (including those of an incomplete record).
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Writes all complete records to [BufferedWriter::output].
+    fn write_records(&mut self) -> std::io::Result<()> {
+        self.last_write = Some(Instant::now());
+        if self.complete == 0 {
+            return Ok(());
+        }
+        let result = self.output.write_all(&self.buffer[..self.complete]);
+        self.buffer.drain(..self.complete);
+        self.complete = 0;
+        result
+    }
+}
+
+impl<W: Write> Write for BufferedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_records()?;
+        self.output.flush()
+    }
+}
+
+impl<W: LogWrite> LogWrite for BufferedWriter<W> {
+    fn is_terminal(&self) -> bool {
+        self.output.is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        self.complete = self.buffer.len();
+        let last_write = *self.last_write.get_or_insert_with(Instant::now);
+        if severity >= self.flush_severity {
+            self.flush()
+        } else if self.buffer.len() >= self.capacity || last_write.elapsed() >= self.flush_interval {
+            self.write_records()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<W: Write> Drop for BufferedWriter<W> {
+    fn drop(&mut self) {
+        self.complete = self.buffer.len();
+        let _ = self.flush();
+    }
+}
+
+/// A [Write] appending to a buffer shared by all of its clones (e.g. for inspecting formatted output in tests).
+///
+/// ```
+/// # use logidize::{*, filter_maps::InvisibleChannelFilterMap, loggers::multi_threaded::SimpleLogger, sinks::WriteSink, writers::SharedBufferWriter};
+/// let buffer = SharedBufferWriter::new();
+/// let logger = SimpleLogger::new(WriteSink::new(buffer.clone(), InvisibleChannelFilterMap));
+/// info!(logger, "hello");
+/// assert!(buffer.contents().ends_with("[INFO][0]: hello\n"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SharedBufferWriter {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SharedBufferWriter {
+    /// Constructs a new [SharedBufferWriter] with an empty buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the locked buffer.
+    pub fn lock(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the buffer's contents (lossily converted to UTF-8).
+    #[must_use]
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.lock()).into_owned()
+    }
+
+    /// Removes and returns the buffer's contents.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.lock())
+    }
+}
+
+impl Write for SharedBufferWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl LogWrite for SharedBufferWriter {}
+
+/// How a [MultiWriter] or [DynMultiWriter] handles a failing writer.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum MultiWritePolicy {
+    /// The first failure is returned immediately, so the following writers aren't written to.
+    FailFast,
+    /// All writers are written to and all failures are returned together.
+    #[default]
+    BestEffort,
+}
+
+/// The failures of the writers of a [MultiWriter] or [DynMultiWriter].
+///
+/// Returned as the inner error of an [std::io::Error] with the [ErrorKind](std::io::ErrorKind) of the first failure.
+#[derive(Debug)]
+pub struct MultiWriteError {
+    /// The failures with the indices of the failing writers
+    /// (the position in [multi_writer!](crate::multi_writer!) or [DynMultiWriter::writers]).
+    pub errors: Vec<(usize, std::io::Error)>,
+}
+
+impl MultiWriteError {
+    fn result(errors: Vec<(usize, std::io::Error)>) -> std::io::Result<()> {
+        match errors.first() {
+            None => Ok(()),
+            Some((_, error)) => Err(std::io::Error::new(error.kind(), MultiWriteError { errors })),
+        }
+    }
+}
+
+impl std::fmt::Display for MultiWriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} writer(s) failed", self.errors.len())?;
+        self.errors.iter().try_for_each(|(index, error)| write!(f, "; #{index}: {error}"))
+    }
+}
+
+impl std::error::Error for MultiWriteError {}
+
+/// A [Write] that writes everything to two [Write]rs (usually created with [multi_writer!](crate::multi_writer!)).
+///
+/// Every write is written entirely to every writer using [Write::write_all()].
+/// Failures are handled according to [MultiWriter::error_policy] and reported as [MultiWriteError].
 #[derive(Clone, Copy, Debug, Default, Hash)]
-pub struct MultiWriter<T1: Write, T2: Write>(pub T1, pub T2);
+pub struct MultiWriter<T1: Write, T2: Write> {
+    /// How failing writers are handled.
+    pub error_policy: MultiWritePolicy,
+    /// The first [Write].
+    pub first: T1,
+    /// The second [Write] (a [MultiWriter] itself for more than two writers).
+    pub second: T2,
+}
+
+impl<T1: Write, T2: Write> MultiWriter<T1, T2> {
+    /// Constructs a new [MultiWriter] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(first: T1, second: T2) -> Self {
+        Self { error_policy: MultiWritePolicy::BestEffort, first, second }
+    }
+
+    fn apply(
+        &mut self,
+        mut f1: impl FnMut(&mut T1) -> std::io::Result<()>,
+        mut f2: impl FnMut(&mut T2) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        let mut errors = Vec::new();
+        if let Err(error) = f1(&mut self.first) {
+            push_flattened(&mut errors, 0, error);
+            if self.error_policy == MultiWritePolicy::FailFast {
+                return MultiWriteError::result(errors);
+            }
+        }
+        if let Err(error) = f2(&mut self.second) {
+            push_flattened(&mut errors, 1, error);
+        }
+        MultiWriteError::result(errors)
+    }
+}
+
+/// Adds `error` at `index`, replacing a [MultiWriteError] (of a nested [MultiWriter]) with its errors.
+fn push_flattened(errors: &mut Vec<(usize, std::io::Error)>, index: usize, error: std::io::Error) {
+    if !error.get_ref().is_some_and(|inner| inner.is::<MultiWriteError>()) {
+        return errors.push((index, error));
+    }
+    let inner = error.into_inner().and_then(|inner| inner.downcast::<MultiWriteError>().ok()).expect("checked above");
+    errors.extend(inner.errors.into_iter().map(|(i, error)| (index + i, error)));
+}
 
 impl<T1: Write, T2: Write> Write for MultiWriter<T1, T2> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.0.write(buf).and(self.1.write(buf))
+        self.apply(|w| w.write_all(buf), |w| w.write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.apply(Write::flush, Write::flush)
+    }
+}
+
+/// A `MultiWriter` is only considered a terminal if all of its writers are.
+impl<T1: LogWrite, T2: LogWrite> LogWrite for MultiWriter<T1, T2> {
+    fn is_terminal(&self) -> bool {
+        self.first.is_terminal() && self.second.is_terminal()
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        self.apply(|w| w.end_record(severity), |w| w.end_record(severity))
+    }
+}
+
+/// A [Write] that writes everything to any number of boxed [LogWrite]rs.
+///
+/// Behaves like [MultiWriter] but the writers can be chosen at runtime.
+///
+/// ```
+/// # use logidize::writers::{DynMultiWriter, MultiWritePolicy, StderrWriter, Write};
+/// let mut writer = DynMultiWriter::new(vec![Box::new(Vec::new()), Box::new(StderrWriter)]);
+/// writer.error_policy = MultiWritePolicy::FailFast;
+/// writer.writers.push(Box::new(std::io::sink()));
+/// writeln!(writer, "written to all writers").unwrap();
+/// ```
+#[derive(Default)]
+pub struct DynMultiWriter {
+    /// How failing writers are handled.
+    pub error_policy: MultiWritePolicy,
+    /// The writers.
+    pub writers: Vec<Box<dyn LogWrite + Send>>,
+}
+
+impl DynMultiWriter {
+    /// Constructs a new [DynMultiWriter] with default settings (that shouldn't be relied upon).
+    #[must_use]
+    pub const fn new(writers: Vec<Box<dyn LogWrite + Send>>) -> Self {
+        Self { error_policy: MultiWritePolicy::BestEffort, writers }
+    }
+
+    fn apply(&mut self, mut f: impl FnMut(&mut dyn LogWrite) -> std::io::Result<()>) -> std::io::Result<()> {
+        let mut errors = Vec::new();
+        for (index, writer) in self.writers.iter_mut().enumerate() {
+            if let Err(error) = f(writer.as_mut()) {
+                errors.push((index, error));
+                if self.error_policy == MultiWritePolicy::FailFast {
+                    break;
+                }
+            }
+        }
+        MultiWriteError::result(errors)
+    }
+}
+
+impl std::fmt::Debug for DynMultiWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynMultiWriter")
+            .field("error_policy", &self.error_policy)
+            .field("writers", &self.writers.len())
+            .finish()
+    }
+}
+
+impl Write for DynMultiWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.apply(|w| w.write_all(buf))?;
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.0.flush().and(self.1.flush())
+        self.apply(|w| w.flush())
     }
 }
 
-/// Creates a `MultiWriter` with the given given writer expressions.
+/// A `DynMultiWriter` is only considered a terminal if all of its writers are.
+impl LogWrite for DynMultiWriter {
+    fn is_terminal(&self) -> bool {
+        self.writers.iter().all(|w| w.is_terminal())
+    }
+
+    fn end_record(&mut self, severity: Level) -> std::io::Result<()> {
+        self.apply(|w| w.end_record(severity))
+    }
+}
+
+/// Creates a [MultiWriter] with the given writer expressions, optionally preceded by a [MultiWritePolicy] for all of them.
+///
+/// ```
+/// # use logidize::{multi_writer, writers::{MultiWritePolicy, StderrWriter}};
+/// let writer = multi_writer!(Vec::new(), StderrWriter);
+/// let writer = multi_writer!(MultiWritePolicy::FailFast; Vec::new(), Vec::new(), StderrWriter);
+/// ```
 #[macro_export]
 macro_rules! multi_writer {
-    ($head:expr, $tail:expr $(,)?) => {
-        $crate::writers::MultiWriter($head, $tail)
+    ($policy:expr; $head:expr, $tail:expr $(,)?) => {
+        $crate::writers::MultiWriter { error_policy: $policy, first: $head, second: $tail }
+    };
+
+    ($policy:expr; $head:expr, $($tail:expr),+ $(,)?) => {
+        $crate::writers::MultiWriter { error_policy: $policy, first: $head, second: $crate::multi_writer!($policy; $($tail),+) }
     };
 
     ($head:expr, $($tail:expr),+ $(,)?) => {
-        $crate::writers::MultiWriter($head, multi_writer!($($tail),+))
+        $crate::multi_writer!($crate::writers::MultiWritePolicy::BestEffort; $head, $($tail),+)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::{
+        filter_maps::InvisibleChannelFilterMap,
+        info,
+        loggers::{Logger, single_threaded::SimpleLogger},
+        sinks::WriteSink,
+    };
+
+    /// A [Write] recording every call to [Write::write()] separately.
+    #[derive(Debug, Default)]
+    struct WriteRecorder(Vec<Vec<u8>>);
+
+    impl Write for WriteRecorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogWrite for WriteRecorder {}
+
+    fn record(writer: &mut BufferedWriter<&mut WriteRecorder>, severity: Level, text: &str) {
+        for line in text.split_inclusive('\n') {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+        writer.end_record(severity).unwrap();
+    }
+
+    #[test]
+    fn test_buffered_writer() {
+        let mut recorder = WriteRecorder::default();
+        let mut writer = BufferedWriter::new(&mut recorder);
+        writer.capacity = 16;
+        writer.flush_interval = Duration::from_secs(3600);
+        record(&mut writer, Level::INFO, "first\n");
+        assert_eq!(writer.buffered(), 6);
+        record(&mut writer, Level::INFO, "second\nline\n");
+        assert_eq!(writer.buffered(), 0);
+        record(&mut writer, Level::DEBUG, "third\n");
+        writer.write_all(b"incomp").unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"lete\n").unwrap();
+        writer.end_record(Level::DEBUG).unwrap();
+        record(&mut writer, Level::ERROR, "error\n");
+        writer.flush_interval = Duration::ZERO;
+        record(&mut writer, Level::DEBUG, "late\n");
+        writer.flush_interval = Duration::from_secs(3600);
+        writer.write_all(b"dropped while incomplete").unwrap();
+        drop(writer);
+        assert_eq!(recorder.0, [
+            &b"first\nsecond\nline\n"[..],
+            b"third\n",
+            b"incomplete\nerror\n",
+            b"late\n",
+            b"dropped while incomplete",
+        ]);
+    }
+
+    #[test]
+    fn test_forwarded_record_ends() {
+        let mut recorder = WriteRecorder::default();
+        let mut writer = LineWriter::new(BufWriter::new(BufferedWriter::new(&mut recorder)));
+        writer.get_mut().get_mut().capacity = 0;
+        writer.write_all(b"first\nsecond").unwrap();
+        writer.end_record(Level::INFO).unwrap();
+        assert_eq!(writer.get_ref().get_ref().output.0, [b"first\nsecond"]);
+    }
+
+    #[test]
+    fn test_buffered_write_sink() {
+        let logger = SimpleLogger::new(WriteSink::new(BufferedWriter::new(Vec::new()), InvisibleChannelFilterMap));
+        logger.sink().output.flush_interval = Duration::from_secs(3600);
+        info!(logger, "buffered");
+        assert_eq!(logger.sink().output.output, b"");
+        logger.flush();
+        assert!(String::from_utf8_lossy(&logger.sink().output.output).ends_with("[INFO][0]: buffered\n"));
+    }
+
+    const STRESS_THREADS: usize = 8;
+    const STRESS_RECORDS: usize = 500;
+
+    fn stress_payload(thread: usize, record: usize) -> String {
+        "x".repeat((thread * 37 + record * 13) % 300 + 50)
+    }
+
+    /// Logs from several threads with separate loggers, so only the writers keep the lines intact.
+    fn stress_stderr() {
+        let threads: Vec<_> = (0..STRESS_THREADS).map(|thread| std::thread::spawn(move || {
+            fn log_records<W: LogWrite>(thread: usize, output: W) -> W {
+                let logger = SimpleLogger::new(WriteSink::new(output, InvisibleChannelFilterMap));
+                for record in 0..STRESS_RECORDS {
+                    info!(logger.channel(thread), "{thread}:{record}:{}", stress_payload(thread, record));
+                }
+                logger.into_sink().output
+            }
+            match thread % 2 {
+                0 => assert!(log_records(thread, AtomicStderrWriter::new()).buffer.is_empty()),
+                _ => assert!(log_records(thread, LockedStderrWriter::new()).lock.is_none()),
+            }
+        })).collect();
+        threads.into_iter().for_each(|thread| thread.join().unwrap());
+    }
+
+    #[test]
+    fn test_line_atomic_stderr() {
+        const CHILD_VAR: &str = "LOGIDIZE_STRESS_CHILD";
+        if std::env::var_os(CHILD_VAR).is_some() {
+            return stress_stderr();
+        }
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "writers::tests::test_line_atomic_stderr", "--nocapture", "--test-threads=1"])
+            .env(CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let mut counts = [0; STRESS_THREADS];
+        for line in stderr.lines() {
+            let (header, message) = line.split_once("]: ").unwrap_or_else(|| panic!("torn line: {line:?}"));
+            let fields: Vec<_> = message.split(':').collect();
+            let [thread, record, payload] = fields[..] else { panic!("torn line: {line:?}") };
+            let (thread, record) = (thread.parse().unwrap(), record.parse().unwrap());
+            assert!(header.ends_with(&format!("[INFO][{thread}")), "torn line: {line:?}");
+            assert_eq!(payload, stress_payload(thread, record), "torn line: {line:?}");
+            counts[thread] += 1;
+        }
+        assert_eq!(counts, [STRESS_RECORDS; STRESS_THREADS]);
+    }
+
+    /// A [Write] accepting at most 3 bytes per call or failing after accepting `limit` bytes.
+    #[derive(Debug, Default)]
+    struct ChoppyWriter {
+        limit: Option<usize>,
+        output: Vec<u8>,
+    }
+
+    impl Write for ChoppyWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.limit.is_some_and(|limit| self.output.len() >= limit) {
+                return Err(std::io::ErrorKind::StorageFull.into());
+            }
+            let len = buf.len().min(3);
+            self.output.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogWrite for ChoppyWriter {}
+
+    fn errors(error: std::io::Error) -> Vec<(usize, std::io::ErrorKind)> {
+        assert_eq!(error.kind(), std::io::ErrorKind::StorageFull);
+        let error = error.into_inner().unwrap().downcast::<MultiWriteError>().unwrap();
+        error.errors.into_iter().map(|(index, error)| (index, error.kind())).collect()
+    }
+
+    #[test]
+    fn test_multi_writer() {
+        let full = || ChoppyWriter { limit: Some(0), output: Vec::new() };
+        let mut writer = multi_writer!(ChoppyWriter::default(), Vec::new(), ChoppyWriter::default());
+        writeln!(writer, "partial writes are completed").unwrap();
+        assert_eq!(writer.first.output, b"partial writes are completed\n");
+        assert_eq!(writer.second.first, b"partial writes are completed\n");
+        assert_eq!(writer.second.second.output, b"partial writes are completed\n");
+
+        let mut writer = multi_writer!(full(), Vec::new(), full());
+        assert_eq!(errors(writer.write(b"best effort").unwrap_err()), [(0, std::io::ErrorKind::StorageFull), (2, std::io::ErrorKind::StorageFull)]);
+        assert_eq!(writer.second.first, b"best effort");
+
+        let mut writer = multi_writer!(MultiWritePolicy::FailFast; Vec::new(), full(), Vec::new());
+        assert_eq!(errors(writer.write(b"fail fast").unwrap_err()), [(1, std::io::ErrorKind::StorageFull)]);
+        assert_eq!(writer.first, b"fail fast");
+        assert!(writer.second.second.is_empty());
+    }
+
+    #[test]
+    fn test_dyn_multi_writer() {
+        let mut writer = DynMultiWriter::new(vec![
+            Box::new(ChoppyWriter::default()),
+            Box::new(ChoppyWriter { limit: Some(4), output: Vec::new() }),
+            Box::new(Vec::new()),
+        ]);
+        writer.write_all(b"ok").unwrap();
+        assert_eq!(errors(writer.write(b" failing").unwrap_err()), [(1, std::io::ErrorKind::StorageFull)]);
+        writer.error_policy = MultiWritePolicy::FailFast;
+        assert_eq!(errors(writer.write(b" again").unwrap_err()), [(1, std::io::ErrorKind::StorageFull)]);
+        assert!(!writer.is_terminal());
+    }
+}
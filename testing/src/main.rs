@@ -1,6 +1,6 @@
 use logidize::{
     *,
-    loggers::{Level, Logger, single_threaded::*},
+    loggers::{Level, Logger, ThreadFormat, single_threaded::*},
     filter_maps::{InvisibleChannelFilterMap, SimpleChannel, StaticSeverityChannelFilterMap},
     sinks::WriteSink,
     writers::{StderrWriter, StdoutWriter}
@@ -49,7 +49,7 @@ fn main() {
             ("Extra-Channel"    , Level::CRITICAL),
         ])
     ));
-    logger.sink().log_thread_id = true;
+    logger.sink().thread_format = Some(ThreadFormat::NameId);
     debug!(logger, "filtered");
     info!(logger, "main");
     debug!(logger.channel(1), "filtered");